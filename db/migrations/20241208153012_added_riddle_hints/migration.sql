@@ -0,0 +1,5 @@
+-- AlterTable
+ALTER TABLE "quest_riddles" ADD COLUMN "hints_used" INTEGER NOT NULL DEFAULT 0;
+
+-- AlterTable
+ALTER TABLE "users" ADD COLUMN "hints_used_today" INTEGER NOT NULL DEFAULT 0;
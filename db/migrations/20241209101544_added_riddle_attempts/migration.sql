@@ -0,0 +1,14 @@
+-- AlterTable
+ALTER TABLE "quest_riddles" ADD COLUMN "attempts" INTEGER NOT NULL DEFAULT 0;
+
+-- CreateTable
+CREATE TABLE "riddle_guesses" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "quest_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "guess" TEXT NOT NULL,
+    "correct" BOOLEAN NOT NULL,
+    "created_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT "riddle_guesses_quest_id_fkey" FOREIGN KEY ("quest_id") REFERENCES "quests" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "riddle_guesses_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,86 @@
+/*
+  Warnings:
+  - Added the required column `game_id` to the `user_states`, `user_cards`, `quests`, `game_target_cards` and `game_winners` tables. 
+    Existing rows are assigned to the earliest game, and dropped if there is no game.
+*/
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_game_states" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "name" TEXT NOT NULL DEFAULT 'Mystery',
+    "murdered_user_id" INTEGER NOT NULL,
+    "created_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "last_daily_refresh" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "archived_on" DATETIME,
+    CONSTRAINT "game_states_murdered_user_id_fkey" FOREIGN KEY ("murdered_user_id") REFERENCES "users" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+INSERT INTO "new_game_states" ("id", "last_daily_refresh", "murdered_user_id") SELECT "id", "last_daily_refresh", "murdered_user_id" FROM "game_states";
+DROP TABLE "game_states";
+ALTER TABLE "new_game_states" RENAME TO "game_states";
+CREATE TABLE "new_user_states" (
+    "game_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "last_login" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "stats_id" INTEGER NOT NULL,
+
+    PRIMARY KEY ("game_id", "user_id"),
+    CONSTRAINT "user_states_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "user_states_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE RESTRICT ON UPDATE CASCADE,
+    CONSTRAINT "user_states_stats_id_fkey" FOREIGN KEY ("stats_id") REFERENCES "stats" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_user_states" ("game_id", "last_login", "stats_id", "user_id") SELECT (SELECT MIN("id") FROM "game_states"), "last_login", "stats_id", "user_id" FROM "user_states" WHERE EXISTS (SELECT 1 FROM "game_states");
+DROP TABLE "user_states";
+ALTER TABLE "new_user_states" RENAME TO "user_states";
+CREATE UNIQUE INDEX "user_states_stats_id_key" ON "user_states"("stats_id");
+CREATE TABLE "new_user_cards" (
+    "game_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "cat_idx" INTEGER NOT NULL,
+    "card_idx" INTEGER NOT NULL,
+    "confirmed" BOOLEAN NOT NULL DEFAULT false,
+
+    PRIMARY KEY ("game_id", "user_id", "cat_idx", "card_idx"),
+    CONSTRAINT "user_cards_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "user_cards_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_user_cards" ("game_id", "card_idx", "cat_idx", "confirmed", "user_id") SELECT (SELECT MIN("id") FROM "game_states"), "card_idx", "cat_idx", "confirmed", "user_id" FROM "user_cards" WHERE EXISTS (SELECT 1 FROM "game_states");
+DROP TABLE "user_cards";
+ALTER TABLE "new_user_cards" RENAME TO "user_cards";
+CREATE TABLE "new_quests" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "game_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "completed" BOOLEAN NOT NULL DEFAULT false,
+    "created_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "quest_type" INTEGER NOT NULL,
+    CONSTRAINT "quests_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "quests_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_quests" ("game_id", "completed", "created_on", "id", "quest_type", "user_id") SELECT (SELECT MIN("id") FROM "game_states"), "completed", "created_on", "id", "quest_type", "user_id" FROM "quests" WHERE EXISTS (SELECT 1 FROM "game_states");
+DROP TABLE "quests";
+ALTER TABLE "new_quests" RENAME TO "quests";
+CREATE TABLE "new_game_target_cards" (
+    "game_id" INTEGER NOT NULL,
+    "cat_idx" INTEGER NOT NULL,
+    "card_idx" INTEGER NOT NULL,
+
+    PRIMARY KEY ("game_id", "cat_idx", "card_idx"),
+    CONSTRAINT "game_target_cards_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_game_target_cards" ("game_id", "card_idx", "cat_idx") SELECT (SELECT MIN("id") FROM "game_states"), "card_idx", "cat_idx" FROM "game_target_cards" WHERE EXISTS (SELECT 1 FROM "game_states");
+DROP TABLE "game_target_cards";
+ALTER TABLE "new_game_target_cards" RENAME TO "game_target_cards";
+CREATE TABLE "new_game_winners" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "game_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    CONSTRAINT "game_winners_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "game_winners_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+INSERT INTO "new_game_winners" ("game_id", "id", "user_id") SELECT (SELECT MIN("id") FROM "game_states"), "id", "user_id" FROM "game_winners" WHERE EXISTS (SELECT 1 FROM "game_states");
+DROP TABLE "game_winners";
+ALTER TABLE "new_game_winners" RENAME TO "game_winners";
+CREATE UNIQUE INDEX "game_winners_game_id_user_id_key" ON "game_winners"("game_id", "user_id");
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
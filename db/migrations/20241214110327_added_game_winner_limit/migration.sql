@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "game_states" ADD COLUMN "winner_limit" INTEGER;
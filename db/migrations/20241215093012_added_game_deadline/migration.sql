@@ -0,0 +1,15 @@
+-- AlterTable
+ALTER TABLE "game_states" ADD COLUMN "deadline" DATETIME;
+
+-- CreateTable
+CREATE TABLE "game_standings" (
+    "game_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "place" INTEGER NOT NULL,
+    "won" BOOLEAN NOT NULL,
+    "confirmed_cards" INTEGER NOT NULL,
+
+    PRIMARY KEY ("game_id", "user_id"),
+    CONSTRAINT "game_standings_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "game_standings_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
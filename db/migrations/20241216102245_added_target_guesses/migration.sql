@@ -0,0 +1,24 @@
+-- AlterTable
+ALTER TABLE "game_states" ADD COLUMN "partial_feedback" BOOLEAN NOT NULL DEFAULT false;
+
+-- CreateTable
+CREATE TABLE "target_guesses" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "game_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "correct" BOOLEAN NOT NULL,
+    "correct_cats" INTEGER,
+    "created_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT "target_guesses_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "target_guesses_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateTable
+CREATE TABLE "target_guess_cards" (
+    "guess_id" INTEGER NOT NULL,
+    "cat_idx" INTEGER NOT NULL,
+    "card_idx" INTEGER NOT NULL,
+
+    PRIMARY KEY ("guess_id", "cat_idx"),
+    CONSTRAINT "target_guess_cards_guess_id_fkey" FOREIGN KEY ("guess_id") REFERENCES "target_guesses" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
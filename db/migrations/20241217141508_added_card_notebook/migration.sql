@@ -0,0 +1,22 @@
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_user_cards" (
+    "game_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "cat_idx" INTEGER NOT NULL,
+    "card_idx" INTEGER NOT NULL,
+    "state" INTEGER NOT NULL DEFAULT 0,
+    "note" TEXT,
+    "source_quest_id" INTEGER,
+
+    PRIMARY KEY ("game_id", "user_id", "cat_idx", "card_idx"),
+    CONSTRAINT "user_cards_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "user_cards_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "user_cards_source_quest_id_fkey" FOREIGN KEY ("source_quest_id") REFERENCES "quests" ("id") ON DELETE SET NULL ON UPDATE CASCADE
+);
+INSERT INTO "new_user_cards" ("game_id", "user_id", "cat_idx", "card_idx", "state") SELECT "game_id", "user_id", "cat_idx", "card_idx", CASE WHEN "confirmed" THEN 2 ELSE 0 END FROM "user_cards";
+DROP TABLE "user_cards";
+ALTER TABLE "new_user_cards" RENAME TO "user_cards";
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
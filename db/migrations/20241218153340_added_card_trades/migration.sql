@@ -0,0 +1,45 @@
+-- AlterTable
+ALTER TABLE "users" ADD COLUMN "trades_today" INTEGER NOT NULL DEFAULT 0;
+
+-- CreateTable
+CREATE TABLE "card_trades" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "game_id" INTEGER NOT NULL,
+    "from_user_id" INTEGER NOT NULL,
+    "to_user_id" INTEGER NOT NULL,
+    "offered_cat_idx" INTEGER NOT NULL,
+    "offered_card_idx" INTEGER NOT NULL,
+    "returned_cat_idx" INTEGER,
+    "returned_card_idx" INTEGER,
+    "status" INTEGER NOT NULL DEFAULT 0,
+    "created_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "resolved_on" DATETIME,
+    CONSTRAINT "card_trades_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "card_trades_from_user_id_fkey" FOREIGN KEY ("from_user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "card_trades_to_user_id_fkey" FOREIGN KEY ("to_user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_user_cards" (
+    "game_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "cat_idx" INTEGER NOT NULL,
+    "card_idx" INTEGER NOT NULL,
+    "state" INTEGER NOT NULL DEFAULT 0,
+    "note" TEXT,
+    "source_quest_id" INTEGER,
+    "source_trade_id" INTEGER,
+
+    PRIMARY KEY ("game_id", "user_id", "cat_idx", "card_idx"),
+    CONSTRAINT "user_cards_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "user_cards_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "user_cards_source_quest_id_fkey" FOREIGN KEY ("source_quest_id") REFERENCES "quests" ("id") ON DELETE SET NULL ON UPDATE CASCADE,
+    CONSTRAINT "user_cards_source_trade_id_fkey" FOREIGN KEY ("source_trade_id") REFERENCES "card_trades" ("id") ON DELETE SET NULL ON UPDATE CASCADE
+);
+INSERT INTO "new_user_cards" ("game_id", "user_id", "cat_idx", "card_idx", "state", "note", "source_quest_id") SELECT "game_id", "user_id", "cat_idx", "card_idx", "state", "note", "source_quest_id" FROM "user_cards";
+DROP TABLE "user_cards";
+ALTER TABLE "new_user_cards" RENAME TO "user_cards";
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
@@ -0,0 +1,5 @@
+-- AlterTable
+ALTER TABLE "quests" ADD COLUMN "succeeded" BOOLEAN NOT NULL DEFAULT false;
+
+-- Riddles answered correctly were succeeded
+UPDATE "quests" SET "succeeded" = true WHERE EXISTS (SELECT 1 FROM "riddle_guesses" rg WHERE rg."quest_id" = "quests"."id" AND rg."correct");
@@ -0,0 +1,12 @@
+-- CreateTable
+CREATE TABLE "user_achievements" (
+    "user_id" INTEGER NOT NULL,
+    "tag" TEXT NOT NULL,
+    "game_id" INTEGER,
+    "unlocked_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "seen" BOOLEAN NOT NULL DEFAULT false,
+
+    PRIMARY KEY ("user_id", "tag"),
+    CONSTRAINT "user_achievements_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "user_achievements_game_id_fkey" FOREIGN KEY ("game_id") REFERENCES "game_states" ("id") ON DELETE SET NULL ON UPDATE CASCADE
+);
@@ -0,0 +1,14 @@
+-- AlterTable
+ALTER TABLE "users" ADD COLUMN "approved" BOOLEAN NOT NULL DEFAULT true;
+
+-- CreateTable
+CREATE TABLE "invite_codes" (
+    "code" TEXT NOT NULL PRIMARY KEY,
+    "created_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "used_by" INTEGER,
+    "used_on" DATETIME,
+    CONSTRAINT "invite_codes_used_by_fkey" FOREIGN KEY ("used_by") REFERENCES "users" ("id") ON DELETE SET NULL ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "invite_codes_used_by_key" ON "invite_codes"("used_by");
@@ -0,0 +1,13 @@
+-- CreateTable
+CREATE TABLE "password_reset_tokens" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "user_id" INTEGER NOT NULL,
+    "token_hash" TEXT NOT NULL,
+    "created_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "expires" DATETIME NOT NULL,
+    "used_on" DATETIME,
+    CONSTRAINT "password_reset_tokens_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "password_reset_tokens_token_hash_key" ON "password_reset_tokens"("token_hash");
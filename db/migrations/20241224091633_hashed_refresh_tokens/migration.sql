@@ -0,0 +1,32 @@
+/*
+  Warnings:
+
+  - Refresh tokens are now looked up by the SHA-256 digest in `token_hash`. Existing plaintext
+    tokens are kept in `token` until the server hashes them on startup, then cleared.
+
+*/
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_refresh_tokens" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "user_id" INTEGER NOT NULL,
+    "repl_id" INTEGER,
+    "created_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "session_started_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "user_agent" TEXT,
+    "expires" DATETIME,
+    "token" TEXT,
+    "token_hash" TEXT,
+    "revoked_on" DATETIME,
+    "revoked_by" TEXT,
+    CONSTRAINT "refresh_tokens_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "refresh_tokens_repl_id_fkey" FOREIGN KEY ("repl_id") REFERENCES "refresh_tokens" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_refresh_tokens" ("created_on", "session_started_on", "user_agent", "expires", "id", "repl_id", "revoked_by", "revoked_on", "token", "user_id") SELECT "created_on", "session_started_on", "user_agent", "expires", "id", "repl_id", "revoked_by", "revoked_on", "token", "user_id" FROM "refresh_tokens";
+DROP TABLE "refresh_tokens";
+ALTER TABLE "new_refresh_tokens" RENAME TO "refresh_tokens";
+CREATE UNIQUE INDEX "refresh_tokens_repl_id_key" ON "refresh_tokens"("repl_id");
+CREATE UNIQUE INDEX "refresh_tokens_token_hash_key" ON "refresh_tokens"("token_hash");
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
@@ -0,0 +1,8 @@
+-- CreateTable
+CREATE TABLE "revoked_access_tokens" (
+    "jti" TEXT NOT NULL PRIMARY KEY,
+    "user_id" INTEGER NOT NULL,
+    "revoked_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "expires" DATETIME NOT NULL,
+    CONSTRAINT "revoked_access_tokens_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,16 @@
+-- CreateTable
+CREATE TABLE "login_attempts" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "email" TEXT NOT NULL,
+    "user_id" INTEGER,
+    "ip" TEXT,
+    "attempted_on" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "succeeded" BOOLEAN NOT NULL,
+    CONSTRAINT "login_attempts_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE SET NULL ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE INDEX "login_attempts_email_attempted_on_idx" ON "login_attempts"("email", "attempted_on");
+
+-- CreateIndex
+CREATE INDEX "login_attempts_ip_attempted_on_idx" ON "login_attempts"("ip", "attempted_on");
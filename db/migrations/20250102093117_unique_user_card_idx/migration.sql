@@ -0,0 +1,2 @@
+-- CreateIndex
+CREATE UNIQUE INDEX "users_card_idx_key" ON "users"("card_idx");
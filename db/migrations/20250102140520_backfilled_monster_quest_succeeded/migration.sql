@@ -0,0 +1,7 @@
+-- Monsters defeated before quests were marked as succeeded were either left at no health,
+-- or rewarded the player with a card revealed by the quest
+UPDATE "quests" SET "succeeded" = true
+WHERE "quest_type" = 0 AND "completed" AND NOT "succeeded" AND (
+    EXISTS (SELECT 1 FROM "monster_states" ms JOIN "stats" s ON ms."stats_id" = s."id" WHERE ms."quest_id" = "quests"."id" AND s."health" <= 0)
+    OR EXISTS (SELECT 1 FROM "user_cards" uc WHERE uc."source_quest_id" = "quests"."id")
+);
@@ -0,0 +1,42 @@
+/*
+  Warnings:
+  - The daily `exhausted`, `riddle_quest_completed`, `guessed_today`, `hints_used_today` and `battles_won_today` 
+    columns are moved from `users` to `user_states`, so each game keeps its own. Existing values are copied
+    to every game the user is in.
+*/
+-- AlterTable
+ALTER TABLE "user_states" ADD COLUMN "riddle_quest_completed" BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE "user_states" ADD COLUMN "exhausted" BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE "user_states" ADD COLUMN "guessed_today" BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE "user_states" ADD COLUMN "hints_used_today" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "user_states" ADD COLUMN "battles_won_today" INTEGER NOT NULL DEFAULT 0;
+
+UPDATE "user_states" SET
+    "riddle_quest_completed" = (SELECT u."riddle_quest_completed" FROM "users" u WHERE u."id" = "user_states"."user_id"),
+    "exhausted" = (SELECT u."exhausted" FROM "users" u WHERE u."id" = "user_states"."user_id"),
+    "guessed_today" = (SELECT u."guessed_today" FROM "users" u WHERE u."id" = "user_states"."user_id"),
+    "hints_used_today" = (SELECT u."hints_used_today" FROM "users" u WHERE u."id" = "user_states"."user_id"),
+    "battles_won_today" = (SELECT u."battles_won_today" FROM "users" u WHERE u."id" = "user_states"."user_id");
+
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_users" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "email" TEXT NOT NULL,
+    "pwd_hash" TEXT NOT NULL,
+    "card_idx" INTEGER NOT NULL,
+    "lvl" INTEGER NOT NULL DEFAULT 1,
+    "xp" INTEGER NOT NULL DEFAULT 0,
+    "trades_today" INTEGER NOT NULL DEFAULT 0,
+    "last_login" DATETIME,
+    "approved" BOOLEAN NOT NULL DEFAULT true,
+    "role" TEXT NOT NULL DEFAULT 'player'
+);
+INSERT INTO "new_users" ("id", "email", "pwd_hash", "card_idx", "lvl", "xp", "trades_today", "last_login", "approved", "role") SELECT "id", "email", "pwd_hash", "card_idx", "lvl", "xp", "trades_today", "last_login", "approved", "role" FROM "users";
+DROP TABLE "users";
+ALTER TABLE "new_users" RENAME TO "users";
+CREATE UNIQUE INDEX "users_email_key" ON "users"("email");
+CREATE UNIQUE INDEX "users_card_idx_key" ON "users"("card_idx");
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
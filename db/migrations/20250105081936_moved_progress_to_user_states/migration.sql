@@ -0,0 +1,32 @@
+/*
+  Warnings:
+  - The `lvl` and `xp` columns are moved from `users` to `user_states`, so each game keeps its own
+    progress and resetting a game resets it. Existing values are copied to every game the user is in.
+*/
+-- AlterTable
+ALTER TABLE "user_states" ADD COLUMN "lvl" INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE "user_states" ADD COLUMN "xp" INTEGER NOT NULL DEFAULT 0;
+
+UPDATE "user_states" SET 
+    "lvl" = (SELECT u."lvl" FROM "users" u WHERE u."id" = "user_states"."user_id"),
+    "xp" = (SELECT u."xp" FROM "users" u WHERE u."id" = "user_states"."user_id");
+
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_users" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "email" TEXT NOT NULL,
+    "pwd_hash" TEXT NOT NULL,
+    "card_idx" INTEGER NOT NULL,
+    "last_login" DATETIME,
+    "approved" BOOLEAN NOT NULL DEFAULT true,
+    "role" TEXT NOT NULL DEFAULT 'player'
+);
+INSERT INTO "new_users" ("id", "email", "pwd_hash", "card_idx", "last_login", "approved", "role") SELECT "id", "email", "pwd_hash", "card_idx", "last_login", "approved", "role" FROM "users";
+DROP TABLE "users";
+ALTER TABLE "new_users" RENAME TO "users";
+CREATE UNIQUE INDEX "users_email_key" ON "users"("email");
+CREATE UNIQUE INDEX "users_card_idx_key" ON "users"("card_idx");
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
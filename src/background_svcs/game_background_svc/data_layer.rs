@@ -0,0 +1,30 @@
+use axum::async_trait;
+use chrono::Utc;
+use sqlx::SqlitePool;
+
+use crate::data_layer_error::Result;
+
+
+#[async_trait]
+pub trait DataLayer : Send + Sync { 
+    ///
+    /// Retrieves the ids of the running games whose deadline has passed
+    /// 
+    async fn get_expired_game_ids(&self) -> Result<Vec<i64>>;
+}
+
+pub struct DbDataLayer {
+    pub db: SqlitePool,
+}
+
+#[async_trait]
+impl DataLayer for DbDataLayer {
+    async fn get_expired_game_ids(&self) -> Result<Vec<i64>> {
+        let now = Utc::now().naive_utc();
+        Ok(
+            sqlx::query!("SELECT id FROM game_states WHERE archived_on IS NULL AND deadline <= ?", now)
+                .fetch_all(&self.db).await?
+                .iter().map(|row| row.id).collect()
+        )
+    }
+}
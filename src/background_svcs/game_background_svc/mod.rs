@@ -0,0 +1,40 @@
+pub mod data_layer;
+pub mod settings;
+
+use crate::services::game_service::GameService;
+
+use std::sync::Arc;
+use self::data_layer::DataLayer;
+
+use log::{info, warn};
+use settings::Settings;
+use tokio_cron_scheduler::{Job, JobSchedulerError};
+
+/// 
+/// Ends every running game whose deadline has passed, closing guessing 
+/// and recording its final standings
+/// 
+pub fn create_deadline_job(data_layer: Arc<dyn DataLayer>, game_service: Arc<dyn GameService>, settings: Settings) -> Result<Job, JobSchedulerError> {
+    Job::new_async(
+        settings.deadline_check_cron, 
+        move |_uuid, _l| { 
+            let dl = data_layer.clone();
+            let gs = game_service.clone();
+            Box::pin(async move {
+                let game_ids = match dl.get_expired_game_ids().await {
+                    Ok(game_ids) => game_ids,
+                    Err(e) => {
+                        warn!("Failed to find games past their deadline: {}", e);
+                        return;
+                    },
+                };
+                for game_id in game_ids {
+                    match gs.end_game(game_id).await {
+                        Ok(_) => info!("Ended game {} at its deadline", game_id),
+                        Err(e) => warn!("Failed to end game {} at its deadline: {}", game_id, e),
+                    }
+                }
+            }) 
+        }
+    )
+}
@@ -0,0 +1,71 @@
+use axum::async_trait;
+use chrono::Utc;
+use sqlx::SqlitePool;
+
+use crate::{data_layer_error::Result, services::token_service::digest_token};
+
+
+#[async_trait]
+pub trait DataLayer : Send + Sync { 
+    ///
+    /// Deletes every refresh token chain whose latest token has been revoked or has expired,
+    /// as none of its tokens can be used anymore. Returns the number of tokens deleted
+    /// 
+    async fn prune_refr_tokens(&self) -> Result<u64>;
+    ///
+    /// Deletes the revoked access tokens which have expired, as they are rejected regardless.
+    /// Returns the number of tokens deleted
+    /// 
+    async fn prune_revoked_access_tokens(&self) -> Result<u64>;
+    ///
+    /// Replaces every refresh token stored in plaintext with its digest. 
+    /// Returns the number of tokens hashed
+    /// 
+    async fn hash_legacy_refr_tokens(&self) -> Result<u64>;
+}
+
+pub struct DbDataLayer {
+    pub db: SqlitePool,
+}
+
+#[async_trait]
+impl DataLayer for DbDataLayer {
+    async fn prune_refr_tokens(&self) -> Result<u64> {
+        let now = Utc::now();
+        Ok(
+            sqlx::query!("
+                WITH RECURSIVE dead_tokens(id) AS (
+                    SELECT id FROM refresh_tokens 
+                    WHERE repl_id IS NULL AND (revoked_on IS NOT NULL OR expires <= ?)
+                    UNION ALL
+                    SELECT rt.id FROM refresh_tokens rt JOIN dead_tokens dt ON rt.repl_id = dt.id
+                )
+                DELETE FROM refresh_tokens WHERE id IN (SELECT id FROM dead_tokens)
+            ", now).execute(&self.db).await?.rows_affected()
+        )
+    }
+
+    async fn prune_revoked_access_tokens(&self) -> Result<u64> {
+        // Bound as naive, matching the format `expires` is stored in
+        let now = Utc::now().naive_utc();
+        Ok(
+            sqlx::query!("DELETE FROM revoked_access_tokens WHERE expires <= ?", now)
+                .execute(&self.db).await?.rows_affected()
+        )
+    }
+
+    async fn hash_legacy_refr_tokens(&self) -> Result<u64> {
+        let legacy_tokens = sqlx::query!("SELECT id, token FROM refresh_tokens WHERE token IS NOT NULL")
+            .fetch_all(&self.db).await?;
+
+        let mut tx = self.db.begin().await?;
+        for legacy_token in &legacy_tokens {
+            let token_hash = legacy_token.token.as_deref().map(digest_token);
+            sqlx::query!("UPDATE refresh_tokens SET token_hash = ?, token = NULL WHERE id = ?", token_hash, legacy_token.id)
+                .execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+
+        Ok(legacy_tokens.len() as u64)
+    }
+}
@@ -0,0 +1,33 @@
+pub mod data_layer;
+pub mod settings;
+
+use std::sync::Arc;
+use self::data_layer::DataLayer;
+
+use log::{info, warn};
+use settings::Settings;
+use tokio_cron_scheduler::{Job, JobSchedulerError};
+
+/// 
+/// Prunes the refresh token chains which have been revoked or 
+/// have expired, so the table doesn't grow with every refresh.
+/// Expired entries of the access token deny-list are pruned as well
+/// 
+pub fn create_prune_job(data_layer: Arc<dyn DataLayer>, settings: Settings) -> Result<Job, JobSchedulerError> {
+    Job::new_async(
+        settings.prune_cron, 
+        move |_uuid, _l| { 
+            let dl = data_layer.clone();
+            Box::pin(async move {
+                match dl.prune_refr_tokens().await {
+                    Ok(count) => info!("Pruned {} dead refresh tokens", count),
+                    Err(e) => warn!("Failed to prune refresh tokens: {}", e),
+                }
+                match dl.prune_revoked_access_tokens().await {
+                    Ok(count) => info!("Pruned {} expired revoked access tokens", count),
+                    Err(e) => warn!("Failed to prune revoked access tokens: {}", e),
+                }
+            }) 
+        }
+    )
+}
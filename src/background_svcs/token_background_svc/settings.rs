@@ -0,0 +1,6 @@
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize)]
+pub struct Settings {
+    pub prune_cron: String
+}
@@ -13,9 +13,9 @@ pub trait DataLayer : Send + Sync {
     /// 
     async fn get_last_user_refr(&self) -> Result<Option<NaiveDateTime>>;
     ///
-    /// Resets all users stats to the given `base_stats`
+    /// Resets all players stats to the stats of their level in each game, given in `lvl_stats` by lvl - 1
     ///
-    async fn reset_user_stats(&self, base_stats: &BaseStats) -> Result<()>;
+    async fn reset_user_stats(&self, lvl_stats: &[BaseStats]) -> Result<()>;
 }
 
 pub struct DbDataLayer {
@@ -27,7 +27,7 @@ impl DataLayer for DbDataLayer {
     async fn get_last_user_refr(&self) -> Result<Option<NaiveDateTime>> {
         // Get the active game state
         let last_daily_refresh = sqlx::query!(
-            "SELECT last_daily_refresh FROM game_states"
+            "SELECT last_daily_refresh FROM game_states WHERE archived_on IS NULL ORDER BY last_daily_refresh DESC"
         ).fetch_optional(&self.db).await?
          .and_then(|row| Some(row.last_daily_refresh));
 
@@ -39,25 +39,31 @@ impl DataLayer for DbDataLayer {
         }
         
     }
-    async fn reset_user_stats(&self, base_stats: &BaseStats) -> Result<()> {
-        // Reset all stats to their base level
-        sqlx::query!(
-            "UPDATE stats SET health = ?, armor = ?, missing_next_turn = FALSE",
-            base_stats.health, base_stats.armor
-        )
+    async fn reset_user_stats(&self, lvl_stats: &[BaseStats]) -> Result<()> {
+        sqlx::query!("UPDATE stats SET missing_next_turn = FALSE")
             .execute(&self.db).await?;
 
-        // Set all player levels to 1
-        sqlx::query!("UPDATE users SET lvl = 1, exhausted = FALSE, riddle_quest_completed = FALSE, guessed_today = FALSE")
+        // Reset the stats of the players at each level to that level's stats
+        for (lvl, stats) in (1..).zip(lvl_stats) {
+            sqlx::query!("
+                UPDATE stats SET health = ?, armor = ? 
+                WHERE id IN (
+                    SELECT stats_id FROM user_states WHERE lvl = ?
+                )
+            ", stats.health, stats.armor, lvl).execute(&self.db).await?;
+        }
+
+        // Restore all players daily allowances, in every game
+        sqlx::query!("UPDATE user_states SET exhausted = FALSE, riddle_quest_completed = FALSE, guessed_today = FALSE, hints_used_today = 0, battles_won_today = 0, trades_today = 0")
             .execute(&self.db).await?;
 
         // Complete all uncompleted quests
         sqlx::query!("UPDATE quests SET completed = TRUE WHERE completed = FALSE")
             .execute(&self.db).await?;
 
-        // Update the game_state's last refresh time to now
+        // Update the running games' last refresh time to now
         let utc_now = Utc::now().naive_utc();
-        sqlx::query!("UPDATE game_states SET last_daily_refresh = ?", utc_now)
+        sqlx::query!("UPDATE game_states SET last_daily_refresh = ? WHERE archived_on IS NULL", utc_now)
             .execute(&self.db).await?;
 
         Ok(())
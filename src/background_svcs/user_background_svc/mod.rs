@@ -25,7 +25,8 @@ pub fn create_refresh_job(data_layer: Arc<dyn DataLayer>, res: Arc<Resources>, s
                 let dl = data_layer.clone();
                 let rs = res.clone();
                 Box::pin(async move {
-                    let ids = &dl.reset_user_stats(&rs.user_base_stats).await.unwrap();
+                    let lvl_stats = (1..=rs.levels.len() as i64).map(|lvl| rs.lvl_stats(lvl)).collect::<Vec<_>>();
+                    let ids = &dl.reset_user_stats(&lvl_stats).await.unwrap();
                     info!("Refreshed user stats @{}. Ids: {:?}", Utc::now(), ids);
                 }) 
             }
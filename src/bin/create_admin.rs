@@ -0,0 +1,69 @@
+use std::{env, fs, io::{self, IsTerminal}, process, sync::Arc};
+
+use dotenvy::dotenv;
+use dotenv_codegen::dotenv;
+
+use lazy_static::lazy_static;
+
+use christmas_2022::{
+    resources::game_resources::{ResourceLoader, Resources},
+    services::{token_service::{data_layer::DbTokenDataLayer, settings::TokenSettings, CoreTokenService},
+    auth_service::{data_layer::DbAuthDataLayer, settings::AuthSettings, AuthService, CoreAuthService}},
+};
+use sqlx::SqlitePool;
+
+lazy_static! {
+    static ref DATABASE_URL: &'static str = {
+        dotenv().ok();
+        dotenv!("DATABASE_URL")
+    };
+}
+
+///
+/// Creates the first admin, who can then assign roles to other users.
+/// Usage: `cargo run --bin create_admin -- <email> <card_idx>`, with the password taken from
+/// the `ADMIN_PASSWORD` environment variable, or piped through stdin, eg. 
+/// `cargo run --bin create_admin -- <email> <card_idx> < pwd.txt`. 
+/// Passwords aren't read from an interactive terminal, as they would be echoed
+///
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().collect();
+    let (Some(email), Some(card_idx)) = (args.get(1), args.get(2).and_then(|idx| idx.parse::<i64>().ok())) else {
+        eprintln!("Usage: create_admin <email> <card_idx>");
+        process::exit(2);
+    };
+
+    let pwd = match env::var("ADMIN_PASSWORD") {
+        Ok(pwd) => pwd,
+        Err(_) if io::stdin().is_terminal() => {
+            eprintln!("Provide the password with ADMIN_PASSWORD, or pipe it through stdin");
+            process::exit(2);
+        },
+        Err(_) => {
+            let mut pwd = String::new();
+            io::stdin().read_line(&mut pwd).unwrap();
+            pwd.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+
+    // Setup state
+    let db = SqlitePool::connect(&DATABASE_URL).await.unwrap();
+    let auth_settings: AuthSettings = serde_json::from_str(&fs::read_to_string("./auth_settings.json").unwrap()).unwrap();
+    auth_settings.validate().expect("auth_settings.json has invalid argon2 parameters");
+    let token_settings: TokenSettings = serde_json::from_str(&fs::read_to_string("./token_settings.json").unwrap()).unwrap();
+    let res = Arc::new(Resources::from_loader(ResourceLoader::load(String::from("./res"))));
+
+    let token_data_layer = Arc::new(DbTokenDataLayer::new(db.clone()));
+    let token_service = Arc::new(CoreTokenService::new(token_settings.clone(), token_data_layer));
+    let auth_data_layer = Arc::new(DbAuthDataLayer::new(db, token_settings));
+    let auth_service = CoreAuthService::new(auth_data_layer, token_service, res, auth_settings);
+
+    match auth_service.bootstrap_admin(email.clone(), pwd, card_idx).await {
+        Ok(user_id) => println!("Created admin {email} ({user_id})"),
+        Err(e) => {
+            eprintln!("Failed to create admin: {e}");
+            process::exit(1);
+        }
+    }
+}
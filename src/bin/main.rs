@@ -8,16 +8,17 @@ use lazy_static::lazy_static;
 
 use christmas_2022::{
     resources::game_resources::{ResourceLoader, Resources}, 
-    services::{token_service::{settings::TokenSettings, CoreTokenService}, 
-    auth_service::{data_layer::DbAuthDataLayer, CoreAuthService}, 
-    game_service::{data_layer::DbGameDataLayer, DbGameService}, quest_service::{data_layer::DbQuestDataLayer, CoreQuestService}, battle_service::{CoreBattleService, data_layer::DataLayer}}, 
-    routes::{auth_routes, game_routes, quest_routes, battle_routes}, background_svcs::user_background_svc::{create_refresh_job, self},
+    services::{token_service::{data_layer::DbTokenDataLayer, settings::TokenSettings, load_jwt_keys, CoreTokenService}, 
+    auth_service::{data_layer::DbAuthDataLayer, settings::AuthSettings, CoreAuthService}, 
+    achievement_service::{data_layer::DbAchievementDataLayer, CoreAchievementService},
+    game_service::{data_layer::DbGameDataLayer, settings::GameSettings, DbGameService}, quest_service::{data_layer::DbQuestDataLayer, kinds::{monster_quest::MonsterQuest, riddle_quest::RiddleQuest, QuestRegistry}, models::QuestKind, settings::QuestSettings, CoreQuestService}, battle_service::{CoreBattleService, data_layer::DataLayer}}, 
+    routes::{auth_routes, game_routes, quest_routes, battle_routes}, background_svcs::{user_background_svc::{create_refresh_job, self}, game_background_svc::{create_deadline_job, self}, token_background_svc::{create_prune_job, data_layer::DataLayer as _, self}},
 };
 use sqlx::SqlitePool;
 use tokio_cron_scheduler::JobScheduler;
 use tower_cookies::CookieManagerLayer;
 use tower_http::trace::{TraceLayer, self};
-use tracing::Level;
+use tracing::{info, Level};
 
 lazy_static! {
     static ref DATABASE_URL: &'static str = {
@@ -33,27 +34,41 @@ async fn main() {
 
     // Setup state
     let db = SqlitePool::connect(&DATABASE_URL).await.unwrap();
+    let auth_settings: AuthSettings = serde_json::from_str(&fs::read_to_string("./auth_settings.json").unwrap()).unwrap();
+    auth_settings.validate().expect("auth_settings.json has invalid argon2 parameters");
     let token_settings: TokenSettings = serde_json::from_str(&fs::read_to_string("./token_settings.json").unwrap()).unwrap();
+    let game_settings: GameSettings = serde_json::from_str(&fs::read_to_string("./game_settings.json").unwrap()).unwrap();
+    let quest_settings: QuestSettings = serde_json::from_str(&fs::read_to_string("./quest_settings.json").unwrap()).unwrap();
     let user_backround_svc_settings: user_background_svc::settings::Settings = serde_json::from_str(&fs::read_to_string("./daily_refresh.json").unwrap()).unwrap();
-    let token_service = Arc::new(CoreTokenService::new(token_settings.clone()));
+    let game_background_svc_settings: game_background_svc::settings::Settings = serde_json::from_str(&fs::read_to_string("./game_deadline.json").unwrap()).unwrap();
+    let token_background_svc_settings: token_background_svc::settings::Settings = serde_json::from_str(&fs::read_to_string("./token_prune.json").unwrap()).unwrap();
+    let token_data_layer = Arc::new(DbTokenDataLayer::new(db.clone()));
+    load_jwt_keys();
+    let token_service = Arc::new(CoreTokenService::new(token_settings.clone(), token_data_layer));
     let res = Arc::new(Resources::from_loader(ResourceLoader::load(String::from("./res"))));
     
     let auth_data_layer = Arc::new(DbAuthDataLayer::new(db.clone(), token_settings.clone()));
-    let auth_service = Arc::new(CoreAuthService::new(auth_data_layer.clone(), token_service.clone())); 
+    let auth_service = Arc::new(CoreAuthService::new(auth_data_layer.clone(), token_service.clone(), res.clone(), auth_settings)); 
+
+    let achievement_data_layer = Arc::new(DbAchievementDataLayer::new(db.clone()));
+    let achievement_service = Arc::new(CoreAchievementService::new(achievement_data_layer, res.clone()));
 
     let game_data_layer = Arc::new(DbGameDataLayer::new(db.clone()));
-    let game_service = Arc::new(DbGameService::new(game_data_layer, auth_service.clone(), res.clone()));
+    let game_service = Arc::new(DbGameService::new(game_data_layer, auth_service.clone(), achievement_service.clone(), res.clone(), game_settings));
 
     let quest_data_layer = Arc::new(DbQuestDataLayer::new(db.clone()));
-    let quest_service = Arc::new(CoreQuestService::new(quest_data_layer, res.clone(), game_service.clone()));
+    let quest_registry = QuestRegistry::default()
+        .with_kind(QuestKind::Monster, Arc::new(MonsterQuest::new(quest_data_layer.clone(), res.clone(), quest_settings.clone())))
+        .with_kind(QuestKind::Riddle, Arc::new(RiddleQuest::new(quest_data_layer.clone(), res.clone(), quest_settings.clone())));
+    let quest_service = Arc::new(CoreQuestService::new(quest_data_layer, res.clone(), game_service.clone(), achievement_service.clone(), quest_settings, quest_registry));
 
     let battle_data_layer = Arc::new(DataLayer::new(db.clone()));
-    let battle_service = Arc::new(CoreBattleService::new(battle_data_layer, quest_service.clone(), res.clone()));
+    let battle_service = Arc::new(CoreBattleService::new(battle_data_layer, quest_service.clone(), achievement_service, res.clone()));
 
     let app = Router::new()
         // Routes
-        .nest("/api/v1/auth", auth_routes::routes(auth_service))
-        .nest("/api/v1/game", game_routes::routes(game_service, token_service.clone()))
+        .nest("/api/v1/auth", auth_routes::routes(auth_service, token_service.clone(), token_settings.clone()))
+        .nest("/api/v1/game", game_routes::routes(game_service.clone(), token_service.clone()))
         .nest("/api/v1/quest", quest_routes::routes(quest_service.clone(), token_service.clone()))
         .nest("/api/v1/battle", battle_routes::routes(token_service, quest_service, battle_service))
         // Logging
@@ -71,8 +86,21 @@ async fn main() {
     let sched = JobScheduler::new().await.unwrap();
     let usr_svc_data_layer = Arc::new(user_background_svc::data_layer::DbDataLayer { db: db.clone() });
     sched.add(create_refresh_job(usr_svc_data_layer, res, user_backround_svc_settings).unwrap()).await.unwrap();
+
+    // Game background deadline service
+    let game_svc_data_layer = Arc::new(game_background_svc::data_layer::DbDataLayer { db: db.clone() });
+    sched.add(create_deadline_job(game_svc_data_layer, game_service, game_background_svc_settings).unwrap()).await.unwrap();
+
+    // Token background prune service. Refresh tokens from before they were stored 
+    // by digest are hashed first, so their sessions stay valid
+    let token_svc_data_layer = Arc::new(token_background_svc::data_layer::DbDataLayer { db: db.clone() });
+    let hashed_count = token_svc_data_layer.hash_legacy_refr_tokens().await.unwrap();
+    if hashed_count > 0 {
+        info!("Hashed {} plaintext refresh tokens", hashed_count);
+    }
+    sched.add(create_prune_job(token_svc_data_layer, token_background_svc_settings).unwrap()).await.unwrap();
     tokio::spawn(async move { sched.start().await.unwrap() });
 
     axum::Server::bind(&addr)
-        .serve(app.into_make_service()).await.unwrap();
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
 }
\ No newline at end of file
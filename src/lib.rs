@@ -3,6 +3,8 @@ pub mod dice;
 
 pub mod background_svcs {
     pub mod user_background_svc;
+    pub mod game_background_svc;
+    pub mod token_background_svc;
 }
 
 pub mod data_layer_error;
@@ -28,6 +30,7 @@ pub mod services {
     pub mod token_service;
     pub mod quest_service;
     pub mod battle_service;
+    pub mod achievement_service;
     // pub mod items_service;
     // pub mod effects_service;
 }
\ No newline at end of file
@@ -1,26 +1,10 @@
-
-
 use std::sync::Arc;
 
 use axum::{async_trait, extract::{FromRequestParts, State}, http::{request::Parts, StatusCode, Request}, TypedHeader, headers::{Authorization, authorization::Bearer}, middleware::Next, response::Response};
 
-use dotenvy::dotenv;
-use dotenv_codegen::dotenv;
-
-use lazy_static::lazy_static;
+use log::error;
 
-use crate::services::token_service::TokenService;
-
-lazy_static! {
-    /// 
-    /// The secret for admin functionality. Admin routes
-    /// will need to provide this secret using "Authorization: Bearer"
-    /// 
-    static ref ADMIN_SECRET: &'static str = {
-        dotenv().ok();
-        dotenv!("ADMIN_SECRET")
-    };
-}
+use crate::services::token_service::{models::Role, TokenService};
 
 ///
 /// Context for a specific authorized user
@@ -29,12 +13,25 @@ lazy_static! {
 pub struct AuthContext { pub user_id: i64 }
 
 ///
-/// Context for admin functionality.
-/// The context itself provides access, there is
-/// no data associated with the context.
+/// Looks up the current role of the authorized user, 
+/// so role changes apply without waiting for a new access token
 /// 
 #[derive(Clone)]
-pub struct AdminContext;
+struct RoleLookup(Arc<dyn TokenService>);
+
+///
+/// Context for game master functionality, ie. managing games and quests.
+/// Derived from an authorized user currently with the game master role or higher
+/// 
+#[derive(Copy, Clone)]
+pub struct GameMasterContext { pub user_id: i64 }
+
+///
+/// Context for admin functionality, ie. managing users.
+/// Derived from an authorized user currently with the admin role
+/// 
+#[derive(Copy, Clone)]
+pub struct AdminContext { pub user_id: i64 }
 
 #[async_trait]
 impl <S : Send + Sync> FromRequestParts<S> for AuthContext {
@@ -51,26 +48,53 @@ impl <S : Send + Sync> FromRequestParts<S> for AuthContext {
     }
 }
 
+#[async_trait]
+impl <S : Send + Sync> FromRequestParts<S> for GameMasterContext {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let ctx = require_role(parts, Role::GameMaster, "Game master only").await?;
+        Ok(GameMasterContext { user_id: ctx.user_id })
+    }
+}
+
 #[async_trait]
 impl <S : Send + Sync> FromRequestParts<S> for AdminContext {
     type Rejection = (StatusCode, String);
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        // Check if the request has an AdminContext in the extensions, and return it if so
-        return if let Some(info) = parts.extensions.get::<AdminContext>() {
-            Ok(info.clone())
-        // Otherwise return an unauthorized message
-        } else {
-            Err((StatusCode::UNAUTHORIZED, "Unauthorized. Admin only".to_string()))
-        }
+        let ctx = require_role(parts, Role::Admin, "Admin only").await?;
+        Ok(AdminContext { user_id: ctx.user_id })
+    }
+}
+
+///
+/// Returns the request's `AuthContext` if the user currently has at least the given `role`.
+/// Otherwise returns unauthorized when not signed in, or forbidden with the `msg`
+/// 
+async fn require_role(parts: &Parts, role: Role, msg: &str) -> Result<AuthContext, (StatusCode, String)> {
+    let unauthorized = || (StatusCode::UNAUTHORIZED, format!("Unauthorized. {msg}"));
+    let (Some(ctx), Some(RoleLookup(token_service))) = (parts.extensions.get::<AuthContext>(), parts.extensions.get::<RoleLookup>()) else {
+        return Err(unauthorized());
+    };
+
+    // The role in the access token may be outdated, so the current one is checked
+    let current_role = token_service.get_current_role(ctx.user_id).await.map_err(|e| {
+        error!("{:?}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "An internal server error has occurred".to_string())
+    })?;
+    match current_role {
+        Some(current_role) if current_role >= role => Ok(*ctx),
+        Some(_) => Err((StatusCode::FORBIDDEN, format!("Forbidden. {msg}"))),
+        None => Err(unauthorized()),
     }
 }
 
 ///
 /// Middleware that handles access token authentication.
 /// Expects token to be provided with header `"Authorization: bearer"`.
-/// Allows both JWT authentication for a specific user, and admin
-/// authentication when provided the admin secret.
+/// Whether the user is allowed game master or admin functionality
+/// is determined by their current role, when requested.
 /// 
 pub async fn auth_middleware<B : Send> (
     bearer: Option<TypedHeader<Authorization<Bearer>>>,
@@ -79,17 +103,12 @@ pub async fn auth_middleware<B : Send> (
     next: Next<B>
 ) -> Response {
     if let Some(bearer) = bearer {
-        let access_token = bearer.token().to_string();
-
-        if access_token == ADMIN_SECRET.to_string() {
-            request.extensions_mut().insert(AdminContext);
-        } else {
-            let result = token_service.verify_access_token(&access_token);
+        let result = token_service.verify_access_token(bearer.token()).await;
 
-            if let Ok(user_id) = result {
-                request.extensions_mut().insert(AuthContext { user_id });
-            }
+        if let Ok(claims) = result {
+            request.extensions_mut().insert(AuthContext { user_id: claims.user_id });
+            request.extensions_mut().insert(RoleLookup(token_service));
         }
     }
     next.run(request).await
-}
\ No newline at end of file
+}
@@ -1,4 +1,4 @@
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 use serde_json;
 use std::{fs, path::PathBuf};
 
@@ -12,6 +12,8 @@ pub struct ResourceLoader {
     pub riddles: Vec<Riddle>,
     pub user_base_stats: BaseStats,
     pub items: Vec<Item>,
+    pub levels: Vec<Level>,
+    pub achievements: Vec<Achievement>,
     // pub spells: Vec<Spell>,
 }
 impl ResourceLoader {
@@ -30,6 +32,10 @@ impl ResourceLoader {
                 .expect("Could not parse file into user base stats");
         let items = serde_json::from_str(&Self::get_file_str(&folder_path, "items.json"))
             .expect("Could not parse file into items");
+        let levels = serde_json::from_str(&Self::get_file_str(&folder_path, "levels.json"))
+            .expect("Could not parse file into levels");
+        let achievements = serde_json::from_str(&Self::get_file_str(&folder_path, "achievements.json"))
+            .expect("Could not parse file into achievements");
         /*let spells = serde_json::from_str(&Self::get_file_str(&folder_path, "spells.json"))
             .expect("Could not parse file into spells");*/
 
@@ -39,7 +45,9 @@ impl ResourceLoader {
             monsters,
             riddles,
             user_base_stats,
-            items
+            items,
+            levels,
+            achievements,
             // items,
             // spells,
         }
@@ -61,9 +69,18 @@ pub struct Resources {
     pub items: Vec<Item>,
     // pub spells: Vec<Spell>,
     pub user_base_stats: BaseStats,
+    pub levels: Vec<Level>,
+    pub achievements: Vec<Achievement>,
 }
 
 impl Resources {
+    ///
+    /// Retrieves the index of the evidence card category with the given `tag`
+    /// 
+    pub fn evd_cat_idx(&self, tag: &str) -> Option<usize> {
+        self.evd_cats_and_cards.iter().position(|cat| cat.tag == tag)
+    }
+
     pub fn from_loader(res_loader: ResourceLoader) -> Self {
         Self {
             evd_cats_and_cards: res_loader.evd_card_cats,
@@ -72,9 +89,60 @@ impl Resources {
             riddles: res_loader.riddles,
             user_base_stats: res_loader.user_base_stats,
             items: res_loader.items,
+            levels: res_loader.levels,
+            achievements: res_loader.achievements,
             // spells: res_loader.spells,
         }
     }
+
+    ///
+    /// The level a player with the given total `xp` has reached. Levels start at 1
+    /// 
+    pub fn lvl_for_xp(&self, xp: i64) -> i64 {
+        (self.levels.iter().filter(|level| level.xp <= xp).count() as i64).max(1)
+    }
+
+    ///
+    /// The XP a player with the given total `xp` still needs to reach the next level.
+    /// Returns None if the player is at the highest level
+    /// 
+    pub fn xp_to_next_lvl(&self, xp: i64) -> Option<i64> {
+        self.levels.iter().find(|level| level.xp > xp).map(|level| level.xp - xp)
+    }
+
+    ///
+    /// The full stats of a player at the given `lvl` - the user base stats,
+    /// plus the stat growth of every level up to and including it
+    /// 
+    pub fn lvl_stats(&self, lvl: i64) -> BaseStats {
+        self.levels.iter().take(lvl.max(0) as usize).fold(
+            self.user_base_stats, 
+            |stats, level| BaseStats { 
+                health: stats.health + level.stat_growth.health, 
+                armor: stats.armor + level.stat_growth.armor 
+            }
+        )
+    }
+
+    ///
+    /// The XP awarded for defeating a monster of the given `monster_lvl`.
+    /// Monsters beyond the level curve award the XP of the highest level
+    /// 
+    pub fn monster_xp(&self, monster_lvl: i64) -> i64 {
+        let idx = (monster_lvl - 1).clamp(0, self.levels.len() as i64 - 1) as usize;
+        self.levels.get(idx).map(|level| level.monster_xp).unwrap_or(0)
+    }
+
+    ///
+    /// The level of monster a player at `pl_lvl` should face - their own level, or the closest
+    /// level below it with monsters. Falls back to the lowest monster level if there is none below.
+    /// Returns None if there are no monsters
+    /// 
+    pub fn monster_lvl_for(&self, pl_lvl: i64) -> Option<i64> {
+        let monster_lvls = || self.monsters.iter().map(|monster| monster.level);
+        monster_lvls().filter(|lvl| *lvl <= pl_lvl).max()
+            .or_else(|| monster_lvls().min())
+    }
 }
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Avatar {
@@ -112,7 +180,57 @@ pub struct Monster {
     pub idle_flv_texts: Vec<String>
 }
 
-#[derive(Default, Serialize, Deserialize, Clone, Copy)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Level {
+    ///
+    /// The total XP required to reach the level
+    /// 
+    pub xp: i64,
+    ///
+    /// The XP awarded for defeating a monster of the level
+    /// 
+    pub monster_xp: i64,
+    ///
+    /// The stats a player gains on reaching the level
+    /// 
+    pub stat_growth: BaseStats,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Achievement {
+    pub tag: String,
+    pub name: String,
+    pub description: String,
+    pub condition: AchievementCondition,
+}
+
+///
+/// The milestone a player must reach to unlock an achievement
+/// 
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum AchievementCondition {
+    #[serde(rename = "monsters_defeated")]
+    MonstersDefeated { count: i64 },
+    #[serde(rename = "battles_lost")]
+    BattlesLost { count: i64 },
+    ///
+    /// Solving `count` distinct riddles, or every riddle if not given
+    /// 
+    #[serde(rename = "riddles_solved")]
+    RiddlesSolved { count: Option<i64> },
+    #[serde(rename = "won_game")]
+    WonGame,
+    #[serde(rename = "won_without_losing_battle")]
+    WonWithoutLosingBattle,
+    ///
+    /// Winning a game within `days` of it being set up
+    /// 
+    #[serde(rename = "won_within_days")]
+    WonWithinDays { days: i64 },
+}
+
+#[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone, Copy)]
 pub struct BaseStats {
     pub health: i64,
     pub armor: i64,
@@ -121,7 +239,30 @@ pub struct BaseStats {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Riddle {
     pub text: String,
-    pub answer: String,
+    ///
+    /// All accepted answers to the riddle. May be given as a single
+    /// string, or a list of alternatives, the first being the primary answer.
+    /// At least one answer must be given
+    /// 
+    #[serde(deserialize_with = "deserialize_answers")]
+    pub answer: Vec<String>,
+}
+
+fn deserialize_answers<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let answers: Vec<String> = serde_with::As::<serde_with::OneOrMany<serde_with::Same>>::deserialize(deserializer)?;
+    if answers.is_empty() || answers.iter().any(|answer| answer.trim().is_empty()) {
+        return Err(de::Error::custom("riddle answers must not be empty"));
+    }
+    Ok(answers)
+}
+
+impl Riddle {
+    ///
+    /// The answer used for scrambling and hints
+    /// 
+    pub fn primary_answer(&self) -> &str {
+        &self.answer[0]
+    }
 }
 
 #[derive(Copy, Clone, Serialize, Deserialize)]
@@ -165,4 +306,87 @@ pub struct Spell {
     #[serde_as(as = "Option<EnumMap>")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effects_other: Option<Vec<EffectType>>,*/
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(xp: i64, monster_xp: i64, health: i64) -> Level {
+        Level { xp, monster_xp, stat_growth: BaseStats { health, armor: 0 } }
+    }
+
+    fn monster(level: i64) -> Monster {
+        Monster {
+            name: String::new(), level, stats: BaseStats::default(), pow_dmg: vec![],
+            attack_flv_texts: vec![], defend_flv_texts: vec![], idle_flv_texts: vec![]
+        }
+    }
+
+    fn res(monster_lvls: &[i64]) -> Resources {
+        Resources::from_loader(ResourceLoader {
+            user_base_stats: BaseStats { health: 10, armor: 0 },
+            levels: vec![level(0, 100, 0), level(100, 150, 5), level(300, 200, 5)],
+            monsters: monster_lvls.iter().map(|lvl| monster(*lvl)).collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_riddle_answers() {
+        let riddle: Riddle = serde_json::from_str(r#"{ "text": "", "answer": "m" }"#).unwrap();
+        assert_eq!(riddle.primary_answer(), "m");
+        let riddle: Riddle = serde_json::from_str(r#"{ "text": "", "answer": ["a", "b"] }"#).unwrap();
+        assert_eq!(riddle.answer, vec!["a", "b"]);
+
+        // Riddles without an answer are rejected when loading
+        assert!(serde_json::from_str::<Riddle>(r#"{ "text": "", "answer": [] }"#).is_err());
+        assert!(serde_json::from_str::<Riddle>(r#"{ "text": "", "answer": " " }"#).is_err());
+    }
+
+    #[test]
+    fn test_level_curve() {
+        let res = res(&[]);
+        assert_eq!(res.lvl_for_xp(0), 1);
+        assert_eq!(res.lvl_for_xp(150), 2);
+        assert_eq!(res.lvl_for_xp(5000), 3);
+        assert_eq!(res.xp_to_next_lvl(150), Some(150));
+        assert_eq!(res.xp_to_next_lvl(300), None);
+        assert_eq!(res.lvl_stats(1), BaseStats { health: 10, armor: 0 });
+        assert_eq!(res.lvl_stats(3), BaseStats { health: 20, armor: 0 });
+        assert_eq!(res.monster_xp(2), 150);
+        assert_eq!(res.monster_xp(9), 200);
+    }
+
+    #[test]
+    fn test_monster_lvl_fallback() {
+        assert_eq!(res(&[1, 2]).monster_lvl_for(2), Some(2));
+        assert_eq!(res(&[1, 2]).monster_lvl_for(5), Some(2));
+        assert_eq!(res(&[2, 3]).monster_lvl_for(1), Some(2));
+        assert_eq!(res(&[]).monster_lvl_for(1), None);
+    }
+
+    #[test]
+    fn test_evd_cat_idx() {
+        let cat = |tag: &str| EvidenceCardCategories { name: String::new(), tag: tag.to_string(), cards: vec![] };
+        let res = Resources::from_loader(ResourceLoader {
+            evd_card_cats: vec![cat("us"), cat("mw")],
+            ..Default::default()
+        });
+        assert_eq!(res.evd_cat_idx("mw"), Some(1));
+        assert_eq!(res.evd_cat_idx("mv"), None);
+    }
+
+    #[test]
+    fn test_achievement_conditions() {
+        let conditions: Vec<AchievementCondition> = serde_json::from_str(r#"[
+            { "kind": "monsters_defeated", "count": 1 },
+            { "kind": "riddles_solved" },
+            { "kind": "won_within_days", "days": 3 }
+        ]"#).unwrap();
+        assert_eq!(conditions, vec![
+            AchievementCondition::MonstersDefeated { count: 1 },
+            AchievementCondition::RiddlesSolved { count: None },
+            AchievementCondition::WonWithinDays { days: 3 },
+        ]);
+    }
+}
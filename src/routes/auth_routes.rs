@@ -1,25 +1,31 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
 use axum::{
-    extract::{State, FromRef},
+    extract::{ConnectInfo, Path, State, FromRef},
+    http::HeaderMap,
     response::IntoResponse,
-    Json, routing::{put, post}, Router
+    Json, routing::{delete, get, put, post}, Router, middleware, TypedHeader, headers::{UserAgent, Authorization, authorization::Bearer}
 };
 
-use tower_cookies::{Cookies, Cookie};
+use tower_cookies::{cookie::{time::Duration, SameSite}, Cookies, Cookie};
 
 use serde::{Deserialize, Serialize};
 
-use crate::services::auth_service::{AuthService, error::AuthServiceError};
+use crate::{
+    middleware::auth_middleware::{auth_middleware, AdminContext, AuthContext},
+    services::{auth_service::{AuthService, error::{AuthServiceError, Result}, models::{PendingUserModel, RegisteredUserModel, SessionModel}}, token_service::{generate_csrf_token, models::Role, settings::TokenSettings, TokenService}},
+};
 
 ///
-/// Payload for creating a Church User
+/// Payload for creating a Church User. Users registering
+/// without an invite code must be approved by an admin
 /// 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CreateUserPayload {
     pub email: String,
     pub pwd: String,
-    pub card_idx: i64
+    pub card_idx: i64,
+    pub invite_code: Option<String>,
 }
 
 ///
@@ -36,6 +42,7 @@ pub struct LoginPayload {
 #[derive(Clone, FromRef)]
 struct AuthRoutesState {
     auth_service: Arc<dyn AuthService>,
+    token_settings: TokenSettings,
 }
 
 ///
@@ -47,13 +54,56 @@ pub struct AuthPayload {
     pub access_token: String,
 }
 
-pub fn routes(auth_service: Arc<dyn AuthService>) -> Router {
+///
+/// Payload for changing the password of the signed in user
+///
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ChangePasswordPayload {
+    pub old_pwd: String,
+    pub new_pwd: String,
+}
+
+///
+/// Payload for resetting a password with an admin-issued reset token
+///
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ResetPasswordPayload {
+    pub token: String,
+    pub new_pwd: String,
+}
+
+///
+/// Payload for an admin setting the role of a user
+///
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SetRolePayload {
+    pub role: Role,
+}
+
+const REFRESH_COOKIE: &str = "refresh-token";
+const CSRF_COOKIE: &str = "csrf-token";
+const CSRF_HEADER: &str = "x-csrf-token";
+
+pub fn routes(auth_service: Arc<dyn AuthService>, token_service: Arc<dyn TokenService>, token_settings: TokenSettings) -> Router {
     Router::new()
         // Routes
         .route("/refresh", put(refresh))
         .route("/login", post(login))
+        .route("/logout", post(logout))
+        .route("/sessions", get(get_sessions))
+        .route("/sessions/:session_id", delete(revoke_session))
+        .route("/register", post(register))
+        .route("/invites", post(create_invite_code))
+        .route("/pending", get(get_pending_users))
+        .route("/pending/:user_id/approve", post(approve_user))
+        .route("/password", put(change_password))
+        .route("/password/reset", post(reset_password))
+        .route("/users/:user_id/reset-token", post(create_pwd_reset_token))
+        .route("/users/:user_id/role", put(set_role))
+        // Auth middleware
+        .layer(middleware::from_fn_with_state(token_service, auth_middleware))
         // State
-        .with_state(AuthRoutesState { auth_service })
+        .with_state(AuthRoutesState { auth_service, token_settings })
 }
 
 
@@ -63,35 +113,151 @@ pub fn routes(auth_service: Arc<dyn AuthService>) -> Router {
 ///
 async fn login(
     State(auth_service): State<Arc<dyn AuthService>>,
+    State(settings): State<TokenSettings>,
     cookies: Cookies,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Json(model): Json<LoginPayload>,
 ) -> impl IntoResponse {
     let res = if let Some(email) = model.email {
-        auth_service.try_accept_creds(email, model.pwd.unwrap()).await
+        let user_agent = user_agent.map(|TypedHeader(user_agent)| user_agent.to_string());
+        auth_service.try_accept_creds(email, model.pwd.unwrap(), user_agent, Some(addr.ip().to_string())).await
     } else {
         let access_token = model.access_token.unwrap();
         auth_service.try_accept_access_token(&access_token).await
     };
     
-    res.and_then(|tokens| {
-        cookies.add(create_cookie(tokens.refresh_token));
-        Ok(Json(tokens.access_token))
+    res.map(|tokens| {
+        add_session_cookies(&cookies, tokens.refresh_token, &settings);
+        Json(tokens.access_token)
     })
 }
 
+///
+/// Signs out of the session of the cookie refresh token, revoking it and removing the cookie.
+/// A valid bearer access token is revoked along with it. As with refreshing, the CSRF cookie
+/// must be echoed back in the `X-CSRF-Token` header
+///
+async fn logout(
+    State(auth_service): State<Arc<dyn AuthService>>,
+    State(settings): State<TokenSettings>,
+    ctx: Option<AuthContext>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    cookies: Cookies,
+) -> Result<()> {
+    let refr_token = cookies.get(REFRESH_COOKIE).ok_or(AuthServiceError::CookieNotFound)?;
+    verify_csrf_token(&headers, &cookies)?;
+    let access_token = ctx.and(bearer).map(|bearer| bearer.token().to_string());
+    auth_service.logout(refr_token.value().to_string(), access_token).await?;
+    remove_session_cookies(&cookies, &settings);
+
+    Ok(())
+}
+
+///
+/// Lists the signed in user's active sessions
+///
+async fn get_sessions(
+    State(auth_service): State<Arc<dyn AuthService>>,
+    ctx: AuthContext,
+    cookies: Cookies,
+) -> Result<Json<Vec<SessionModel>>> {
+    let refr_token = cookies.get(REFRESH_COOKIE).map(|cookie| cookie.value().to_string());
+    Ok(Json(auth_service.get_sessions(ctx.user_id, refr_token).await?))
+}
+
+///
+/// Signs out of one of the signed in user's sessions
+///
+async fn revoke_session(State(auth_service): State<Arc<dyn AuthService>>, Path(session_id): Path<i64>, ctx: AuthContext) -> Result<()> {
+    auth_service.revoke_session(ctx.user_id, session_id).await
+}
+
+///
+/// Registers a new user, claiming the avatar of the given person card
+///
+async fn register(
+    State(auth_service): State<Arc<dyn AuthService>>,
+    Json(model): Json<CreateUserPayload>,
+) -> Result<Json<RegisteredUserModel>> {
+    Ok(Json(auth_service.register(model.email, model.pwd, model.card_idx, model.invite_code).await?))
+}
+
+///
+/// Creates a single-use invite code, which approves the user registering with it
+///
+async fn create_invite_code(State(auth_service): State<Arc<dyn AuthService>>, _admin: AdminContext) -> Result<Json<String>> {
+    Ok(Json(auth_service.create_invite_code().await?))
+}
+
+async fn get_pending_users(State(auth_service): State<Arc<dyn AuthService>>, _admin: AdminContext) -> Result<Json<Vec<PendingUserModel>>> {
+    Ok(Json(auth_service.get_pending_users().await?))
+}
+
+async fn approve_user(State(auth_service): State<Arc<dyn AuthService>>, Path(user_id): Path<i64>, _admin: AdminContext) -> Result<()> {
+    auth_service.approve_user(user_id).await
+}
+
+///
+/// Changes the signed in user's password, signing out all of their sessions
+///
+async fn change_password(
+    State(auth_service): State<Arc<dyn AuthService>>,
+    ctx: AuthContext,
+    Json(model): Json<ChangePasswordPayload>,
+) -> Result<()> {
+    auth_service.change_password(ctx.user_id, model.old_pwd, model.new_pwd).await
+}
+
+///
+/// Issues a one-time password reset token for the user, for an admin to pass on
+///
+async fn create_pwd_reset_token(State(auth_service): State<Arc<dyn AuthService>>, Path(user_id): Path<i64>, _admin: AdminContext) -> Result<Json<String>> {
+    Ok(Json(auth_service.create_pwd_reset_token(user_id).await?))
+}
+
+///
+/// Sets the role of the user. It takes effect for role checks immediately,
+/// and in the user's access token once renewed
+///
+async fn set_role(
+    State(auth_service): State<Arc<dyn AuthService>>,
+    Path(user_id): Path<i64>,
+    admin: AdminContext,
+    Json(model): Json<SetRolePayload>,
+) -> Result<()> {
+    auth_service.set_role(admin.user_id, user_id, model.role).await
+}
+
+///
+/// Sets a new password by redeeming a password reset token, signing out all of the user's sessions
+///
+async fn reset_password(
+    State(auth_service): State<Arc<dyn AuthService>>,
+    Json(model): Json<ResetPasswordPayload>,
+) -> Result<()> {
+    auth_service.reset_password(model.token, model.new_pwd).await
+}
+
 ///
 /// Attempts to refresh a session with a cookie refresh token,
-/// creating a new access and refresh token from the given one
+/// creating a new access and refresh token from the given one.
+/// The CSRF cookie must be echoed back in the `X-CSRF-Token` header
 ///
 async fn refresh(
     State(auth_service): State<Arc<dyn AuthService>>,
+    State(settings): State<TokenSettings>,
+    headers: HeaderMap,
     cookies: Cookies,
 ) -> impl IntoResponse {
-    return match cookies.get("refresh-token") {
+    return match cookies.get(REFRESH_COOKIE) {
         Some(refr_token) => {
+            verify_csrf_token(&headers, &cookies)?;
+
             let refr_token = refr_token.value().to_string();
             let tokens = auth_service.try_accept_refresh(refr_token.clone()).await?;
-            cookies.add(create_cookie(tokens.refresh_token));
+            add_session_cookies(&cookies, tokens.refresh_token, &settings);
 
             Ok(Json(tokens.access_token))
         }
@@ -99,9 +265,41 @@ async fn refresh(
     };
 }
 
-fn create_cookie(refr_token: String) -> Cookie<'static> {
-    // Create the refresh token cookie
-    let mut cookie = Cookie::new("refresh-token", refr_token);
-    cookie.set_http_only(true);
+///
+/// Checks the double-submitted CSRF token, ie. that the `X-CSRF-Token` header
+/// matches the value of the CSRF cookie
+///
+fn verify_csrf_token(headers: &HeaderMap, cookies: &Cookies) -> Result<()> {
+    let cookie = cookies.get(CSRF_COOKIE);
+    let header = headers.get(CSRF_HEADER).and_then(|header| header.to_str().ok());
+
+    match (cookie, header) {
+        (Some(cookie), Some(header)) if !header.is_empty() && cookie.value() == header => Ok(()),
+        _ => Err(AuthServiceError::CsrfTokenMismatch),
+    }
+}
+
+///
+/// Adds the refresh token cookie, along with a new CSRF cookie. The CSRF cookie
+/// isn't `HttpOnly` and is scoped to the whole site, so the client can read it
+///
+fn add_session_cookies(cookies: &Cookies, refr_token: String, settings: &TokenSettings) {
+    cookies.add(create_cookie(REFRESH_COOKIE, refr_token, &settings.refr_cookie_path, true, settings));
+    cookies.add(create_cookie(CSRF_COOKIE, generate_csrf_token(), "/", false, settings));
+}
+
+fn remove_session_cookies(cookies: &Cookies, settings: &TokenSettings) {
+    cookies.remove(create_cookie(REFRESH_COOKIE, String::new(), &settings.refr_cookie_path, true, settings));
+    cookies.remove(create_cookie(CSRF_COOKIE, String::new(), "/", false, settings));
+}
+
+fn create_cookie(name: &'static str, value: String, path: &str, http_only: bool, settings: &TokenSettings) -> Cookie<'static> {
+    // Create the cookie, living as long as the refresh token
+    let mut cookie = Cookie::new(name, value);
+    cookie.set_http_only(http_only);
+    cookie.set_secure(settings.cookie_secure);
+    cookie.set_same_site(SameSite::from(settings.cookie_same_site));
+    cookie.set_path(path.to_string());
+    cookie.set_max_age(Duration::seconds(settings.refr_token_lifetime_s));
     cookie
-}
\ No newline at end of file
+}
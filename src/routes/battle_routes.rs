@@ -1,6 +1,6 @@
 use std::{sync::Arc, ops::ControlFlow};
 
-use axum::{Router, routing::get, extract::{ws::{Message, WebSocket, WebSocketUpgrade}, FromRef, State}, response::IntoResponse};
+use axum::{Router, routing::get, extract::{ws::{Message, WebSocket, WebSocketUpgrade}, FromRef, Path, State}, response::IntoResponse};
 use log::error;
 
 use crate::services::{battle_service::BattleService, quest_service::{error::QuestServiceError, QuestService}, token_service::TokenService};
@@ -15,7 +15,7 @@ pub struct BattleRoutesState {
 pub fn routes(token_service: Arc<dyn TokenService>, quest_service: Arc<dyn QuestService>, battle_service: Arc<dyn BattleService>) -> Router {
     Router::new()
         // Routes
-        .route("/", get(ws_handler))
+        .route("/:game_id", get(ws_handler))
         // State
         .with_state(BattleRoutesState { token_service, battle_service, quest_service })
 }
@@ -27,6 +27,7 @@ pub fn routes(token_service: Arc<dyn TokenService>, quest_service: Arc<dyn Quest
 /// as well as things from HTTP headers such as user-agent of the browser etc.
 async fn ws_handler(
     ws: WebSocketUpgrade,
+    Path(game_id): Path<i64>,
     State(token_service): State<Arc<dyn TokenService>>,
     State(battle_service): State<Arc<dyn BattleService>>,
     State(quest_service): State<Arc<dyn QuestService>>,
@@ -35,13 +36,14 @@ async fn ws_handler(
     // finalize the upgrade process by returning upgrade callback.
     // we can customize the callback by sending additional info such as address.
     ws.on_upgrade(
-        move |socket| handle_socket(socket, token_service, battle_service, quest_service)
+        move |socket| handle_socket(socket, game_id, token_service, battle_service, quest_service)
     )
 }
 
 /// Actual websocket statemachine (one will be spawned per connection)
 async fn handle_socket(
     mut socket: WebSocket,
+    game_id: i64,
     token_service: Arc<dyn TokenService>, 
     battle_service: Arc<dyn BattleService>,
     quest_service: Arc<dyn QuestService>
@@ -53,7 +55,7 @@ async fn handle_socket(
     }
 
     let user_id = match socket.recv().await {
-        Some(Ok(msg)) => Some(token_service.verify_access_token(&msg.into_text().unwrap()).unwrap()),
+        Some(Ok(Message::Text(access_token))) => token_service.verify_access_token(&access_token).await.ok().map(|claims| claims.user_id),
         _ => None
     };
     if user_id.is_none() {
@@ -62,8 +64,8 @@ async fn handle_socket(
     }
     let user_id = user_id.unwrap();
 
-    // Ensure the user is currently on a quest - if not, return BAD REQUEST
-    if let Err(e) = quest_service.get_quest(user_id).await {
+    // Ensure the user is currently on a quest in the game - if not, return BAD REQUEST
+    if let Err(e) = quest_service.get_quest(user_id, game_id).await {
         if let QuestServiceError::UserNotOnQuest = e {
             socket.send(Message::Text("User does not have an active battle quest".to_string()))
                 .await.unwrap();
@@ -71,7 +73,7 @@ async fn handle_socket(
         return;
     }
 
-    let setup = battle_service.setup(user_id).await;
+    let setup = battle_service.setup(user_id, game_id).await;
 
     if let Err(e) = setup { 
         error!("{:?}", e);
@@ -83,7 +85,7 @@ async fn handle_socket(
     }
 
     while let Some(Ok(msg)) = socket.recv().await {
-        match process_message(msg, user_id, battle_service.clone()).await {
+        match process_message(msg, user_id, game_id, battle_service.clone()).await {
             ControlFlow::Continue(Some(msg)) => { 
                 if let Err(e) = socket.send(msg).await {
                     error!("Error sending message to user_id {user_id}: `{:?}`", e);
@@ -106,7 +108,7 @@ async fn handle_socket(
 }
 
 /// helper to print contents of messages to stdout. Has special treatment for Close.
-async fn process_message(msg: Message, user_id: i64, battle_service: Arc<dyn BattleService>) -> ControlFlow<Option<Message>, Option<Message>> {
+async fn process_message(msg: Message, user_id: i64, game_id: i64, battle_service: Arc<dyn BattleService>) -> ControlFlow<Option<Message>, Option<Message>> {
     match msg {
         Message::Text(t) => {
             // Parse the client command from the possible choices in the battle
@@ -115,7 +117,7 @@ async fn process_message(msg: Message, user_id: i64, battle_service: Arc<dyn Bat
                 if let Ok(val) = msg.parse::<i64>() {
                     match cmd {
                         "Attack" => {
-                            match battle_service.attack(user_id, val).await {
+                            match battle_service.attack(user_id, game_id, val).await {
                                 Ok(round_res) => {
                                     return if round_res.battle_completed() {
                                         ControlFlow::Break(Some(round_res.to_ws_msg()))
@@ -127,7 +129,7 @@ async fn process_message(msg: Message, user_id: i64, battle_service: Arc<dyn Bat
                             }
                         }
                         "Item" => {
-                            match battle_service.use_item(user_id, val).await {
+                            match battle_service.use_item(user_id, game_id, val).await {
                                 Ok(round_res) => {
                                     return if round_res.battle_completed() {
                                         ControlFlow::Break(Some(round_res.to_ws_msg()))
@@ -151,7 +153,7 @@ async fn process_message(msg: Message, user_id: i64, battle_service: Arc<dyn Bat
                 // If the command does not have arguments
                 match t.as_ref() {
                     "Defend" => {
-                        match battle_service.defend(user_id).await {
+                        match battle_service.defend(user_id, game_id).await {
                             Ok(round_res) => return if round_res.battle_completed() {
                                 ControlFlow::Break(Some(round_res.to_ws_msg()))
                             } else {
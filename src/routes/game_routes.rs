@@ -1,9 +1,9 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use axum::{Router, routing::{post, get}, extract::{State, FromRef}, Json, middleware};
+use axum::{Router, routing::{post, get}, extract::{Path, State, FromRef}, Json, middleware};
 
 use crate::{
-    middleware::auth_middleware::{auth_middleware, AdminContext, AuthContext}, services::{game_service::{error::Result, models::{GameInitialStateModel, GameStateModel, GuessResult, UserCardModel}, GameService}, token_service::TokenService}
+    middleware::auth_middleware::{auth_middleware, AuthContext, GameMasterContext}, services::{game_service::{error::Result, models::{ArchivedGameModel, GameInitialStateModel, GameModel, GameSetupModel, GameStateModel, GuessResult, LeaderboardEntryModel, NotebookCardModel, NotebookUpdateModel, CardModel, TradeModel, TradeOfferModel}, GameService}, token_service::TokenService}
 };
 
 #[derive(Clone, FromRef)]
@@ -15,9 +15,18 @@ pub fn routes(game_service: Arc<dyn GameService>, token_service: Arc<dyn TokenSe
     let router = Router::new()
         // Routes
         .route("/setup", post(setup_game))
-        .route("/state", get(game_state))
-        .route("/guess", post(guess_target_cards))
-        .route("/update-card", post(update_user_card))
+        .route("/list", get(get_games))
+        .route("/:game_id/join", post(join_game))
+        .route("/:game_id/end", post(end_game))
+        .route("/:game_id/reveal", get(reveal_game))
+        .route("/:game_id/reset", post(reset_game))
+        .route("/:game_id/state", get(game_state))
+        .route("/:game_id/guess", post(guess_target_cards))
+        .route("/:game_id/leaderboard", get(get_leaderboard))
+        .route("/:game_id/notebook", get(get_notebook).put(update_notebook))
+        .route("/:game_id/trades", get(get_trades).post(offer_trade))
+        .route("/:game_id/trades/:trade_id/accept", post(accept_trade))
+        .route("/:game_id/trades/:trade_id/decline", post(decline_trade))
         // Auth middleware
         .layer(middleware::from_fn_with_state(token_service, auth_middleware))
         // State
@@ -26,19 +35,64 @@ pub fn routes(game_service: Arc<dyn GameService>, token_service: Arc<dyn TokenSe
     router
 }
 
-async fn setup_game(State(game_service): State<Arc<dyn GameService>>, _admin: AdminContext) -> Result<Json<GameInitialStateModel>> {
-    Ok(Json(game_service.setup_game().await?))
+async fn setup_game(State(game_service): State<Arc<dyn GameService>>, _gm: GameMasterContext, setup: Option<Json<GameSetupModel>>) -> Result<Json<GameInitialStateModel>> {
+    let setup = setup.map(|Json(setup)| setup).unwrap_or_default();
+    Ok(Json(game_service.setup_game(setup).await?))
 }
 
-async fn game_state(State(game_service): State<Arc<dyn GameService>>, ctx: AuthContext) -> Result<Json<GameStateModel>> {
-    Ok(Json(game_service.game_state(ctx.user_id).await?))
+async fn get_games(State(game_service): State<Arc<dyn GameService>>, ctx: AuthContext) -> Result<Json<Vec<GameModel>>> {
+    Ok(Json(game_service.get_games(ctx.user_id).await?))
 }
 
-async fn guess_target_cards(State(game_service): State<Arc<dyn GameService>>, ctx: AuthContext, guess: Json<Vec<i64>>) -> Result<Json<GuessResult>> {
-    Ok(Json(game_service.guess_target_cards(ctx.user_id, &guess).await?))
+async fn join_game(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, ctx: AuthContext) -> Result<()> {
+    game_service.join_game(game_id, ctx.user_id).await
 }
 
-async fn update_user_card(State(game_service): State<Arc<dyn GameService>>, ctx: AuthContext, card: Json<UserCardModel>) -> Result<()> {
-    game_service.update_user_card(ctx.user_id, card.cat_idx, card.card_idx, card.confirmed).await?;
-    Ok(())
-}
\ No newline at end of file
+async fn end_game(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, _gm: GameMasterContext) -> Result<Json<ArchivedGameModel>> {
+    Ok(Json(game_service.end_game(game_id).await?))
+}
+
+async fn reveal_game(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, _ctx: AuthContext) -> Result<Json<ArchivedGameModel>> {
+    Ok(Json(game_service.reveal_game(game_id).await?))
+}
+
+async fn reset_game(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, _gm: GameMasterContext) -> Result<()> {
+    game_service.reset_game(game_id).await
+}
+
+async fn game_state(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, ctx: AuthContext) -> Result<Json<GameStateModel>> {
+    Ok(Json(game_service.game_state(game_id, ctx.user_id).await?))
+}
+
+async fn guess_target_cards(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, ctx: AuthContext, guess: Json<HashMap<String, i64>>) -> Result<Json<GuessResult>> {
+    Ok(Json(game_service.guess_target_cards(game_id, ctx.user_id, &guess).await?))
+}
+
+async fn get_leaderboard(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, ctx: AuthContext) -> Result<Json<Vec<LeaderboardEntryModel>>> {
+    Ok(Json(game_service.get_leaderboard(game_id, ctx.user_id).await?))
+}
+
+async fn get_notebook(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, ctx: AuthContext) -> Result<Json<Vec<NotebookCardModel>>> {
+    game_service.ensure_pl_was_in_game(game_id, ctx.user_id).await?;
+    Ok(Json(game_service.get_notebook(game_id, ctx.user_id).await?))
+}
+
+async fn update_notebook(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, ctx: AuthContext, Json(updates): Json<Vec<NotebookUpdateModel>>) -> Result<Json<Vec<NotebookCardModel>>> {
+    Ok(Json(game_service.update_notebook(game_id, ctx.user_id, updates).await?))
+}
+
+async fn get_trades(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, ctx: AuthContext) -> Result<Json<Vec<TradeModel>>> {
+    Ok(Json(game_service.get_trades(game_id, ctx.user_id).await?))
+}
+
+async fn offer_trade(State(game_service): State<Arc<dyn GameService>>, Path(game_id): Path<i64>, ctx: AuthContext, Json(offer): Json<TradeOfferModel>) -> Result<Json<TradeModel>> {
+    Ok(Json(game_service.offer_trade(game_id, ctx.user_id, offer).await?))
+}
+
+async fn accept_trade(State(game_service): State<Arc<dyn GameService>>, Path((game_id, trade_id)): Path<(i64, i64)>, ctx: AuthContext, Json(card): Json<CardModel>) -> Result<Json<TradeModel>> {
+    Ok(Json(game_service.accept_trade(game_id, ctx.user_id, trade_id, card).await?))
+}
+
+async fn decline_trade(State(game_service): State<Arc<dyn GameService>>, Path((game_id, trade_id)): Path<(i64, i64)>, ctx: AuthContext) -> Result<Json<TradeModel>> {
+    Ok(Json(game_service.decline_trade(game_id, ctx.user_id, trade_id).await?))
+}
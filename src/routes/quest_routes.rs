@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use axum::{Router, routing::{post, get}, extract::{FromRef, Path, State}, Json, middleware};
 
-use crate::{middleware::auth_middleware::{AuthContext, auth_middleware}, services::{quest_service::{error::Result, QuestService, models::{QuestStateModel, RiddleStatus}}, token_service::TokenService}};
+use crate::{middleware::auth_middleware::{AuthContext, GameMasterContext, auth_middleware}, services::{quest_service::{error::Result, QuestService, models::{QuestKind, QuestRiddleModel, QuestStateModel, RiddleGuessModel, RiddleStatus}}, token_service::TokenService}};
 
 #[derive(Clone, FromRef)]
 pub struct QuestRoutesState {
@@ -12,9 +12,11 @@ pub struct QuestRoutesState {
 pub fn routes(quest_service: Arc<dyn QuestService>, token_service: Arc<dyn TokenService>) -> Router {
     Router::new()
         // Routes
-        .route("/create/:typ", post(create_quest))
-        .route("/guess-riddle/:answer", post(guess_riddle))
-        .route("/current", get(get_quest))
+        .route("/:game_id/create/:typ", post(create_quest))
+        .route("/:game_id/guess-riddle/:answer", post(guess_riddle))
+        .route("/:game_id/riddle-hint", post(use_riddle_hint))
+        .route("/riddle-guesses", get(get_riddle_guesses))
+        .route("/:game_id/current", get(get_quest))
         // Auth middleware
         .layer(middleware::from_fn_with_state(token_service, auth_middleware))
         // State
@@ -23,23 +25,40 @@ pub fn routes(quest_service: Arc<dyn QuestService>, token_service: Arc<dyn Token
 
 async fn create_quest(
     State(quest_service): State<Arc<dyn QuestService>>,
-    Path(typ): Path<i64>,
+    Path((game_id, typ)): Path<(i64, String)>,
     ctx: AuthContext,
 ) -> Result<Json<QuestStateModel>> {
-    Ok(Json(quest_service.generate_quest(ctx.user_id, typ).await?))
+    let kind = typ.parse::<QuestKind>()?;
+    Ok(Json(quest_service.generate_quest(ctx.user_id, game_id, kind).await?))
 }
 
 async fn guess_riddle(
     State(quest_service): State<Arc<dyn QuestService>>,
-    Path(answer): Path<String>,
+    Path((game_id, answer)): Path<(i64, String)>,
     ctx: AuthContext,
 ) -> Result<Json<RiddleStatus>> {
-    Ok(Json(quest_service.guess_riddle(ctx.user_id, answer).await?))
+    Ok(Json(quest_service.guess_riddle(ctx.user_id, game_id, answer).await?))
+}
+
+async fn use_riddle_hint(
+    State(quest_service): State<Arc<dyn QuestService>>,
+    Path(game_id): Path<i64>,
+    ctx: AuthContext,
+) -> Result<Json<QuestRiddleModel>> {
+    Ok(Json(quest_service.use_riddle_hint(ctx.user_id, game_id).await?))
+}
+
+async fn get_riddle_guesses(
+    State(quest_service): State<Arc<dyn QuestService>>,
+    _gm: GameMasterContext,
+) -> Result<Json<Vec<RiddleGuessModel>>> {
+    Ok(Json(quest_service.get_riddle_guesses().await?))
 }
 
 async fn get_quest(
     State(quest_service): State<Arc<dyn QuestService>>,
+    Path(game_id): Path<i64>,
     ctx: AuthContext
 ) -> Result<Json<QuestStateModel>> {
-    Ok(Json(quest_service.get_quest(ctx.user_id).await?))
+    Ok(Json(quest_service.get_quest(ctx.user_id, game_id).await?))
 }
@@ -0,0 +1,130 @@
+use axum::async_trait;
+use chrono::NaiveDateTime;
+use derive_more::Constructor;
+use sqlx::SqlitePool;
+
+use crate::{data_layer_error::Result, services::quest_service::models::QuestKind};
+
+///
+/// An achievement unlocked by a user, as stored
+/// 
+pub struct UserAchievementEntity {
+    pub tag: String,
+    pub unlocked_on: NaiveDateTime,
+    pub seen: bool,
+}
+
+#[async_trait]
+pub trait AchievementDataLayer : Send + Sync {
+    ///
+    /// Retrieves every achievement the user has unlocked, oldest first
+    /// 
+    async fn get_achievements(&self, user_id: i64) -> Result<Vec<UserAchievementEntity>>;
+    ///
+    /// Marks every achievement the user has unlocked as seen
+    /// 
+    async fn mark_achievements_seen(&self, user_id: i64) -> Result<()>;
+    ///
+    /// Unlocks the achievement with the given `tag` for the user, in the game with the 
+    /// given `game_id`, if any
+    /// 
+    async fn unlock_achievement<'a>(&self, user_id: i64, tag: &'a str, game_id: Option<i64>) -> Result<()>;
+    ///
+    /// Retrieves the number of monster quests the user has succeeded in, across all games
+    /// 
+    async fn get_pl_monsters_defeated(&self, user_id: i64) -> Result<i64>;
+    ///
+    /// Retrieves the number of monster quests the user has failed, across all games
+    /// 
+    async fn get_pl_battles_lost(&self, user_id: i64) -> Result<i64>;
+    ///
+    /// Retrieves the number of distinct riddles the user has solved, across all games
+    /// 
+    async fn get_pl_riddles_solved(&self, user_id: i64) -> Result<i64>;
+    ///
+    /// Checks if the user has failed any monster quest in the game with the given `game_id`
+    /// 
+    async fn pl_lost_battle_in_game(&self, game_id: i64, user_id: i64) -> Result<bool>;
+    ///
+    /// Retrieves when the game with the given `game_id` was set up
+    /// 
+    async fn get_game_created_on(&self, game_id: i64) -> Result<NaiveDateTime>;
+}
+
+#[derive(Constructor)]
+pub struct DbAchievementDataLayer {
+    db: SqlitePool
+}
+
+#[async_trait]
+impl AchievementDataLayer for DbAchievementDataLayer {
+    async fn get_achievements(&self, user_id: i64) -> Result<Vec<UserAchievementEntity>> {
+        Ok(
+            sqlx::query_as!(UserAchievementEntity,
+                "SELECT tag, unlocked_on, seen FROM user_achievements WHERE user_id = ? ORDER BY unlocked_on ASC, tag ASC",
+                user_id
+            ).fetch_all(&self.db).await?
+        )
+    }
+
+    async fn mark_achievements_seen(&self, user_id: i64) -> Result<()> {
+        sqlx::query!("UPDATE user_achievements SET seen = TRUE WHERE user_id = ? AND seen = FALSE", user_id)
+            .execute(&self.db).await?;
+        Ok(())
+    }
+
+    async fn unlock_achievement<'a>(&self, user_id: i64, tag: &'a str, game_id: Option<i64>) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO user_achievements (user_id, tag, game_id) VALUES (?, ?, ?) ON CONFLICT DO NOTHING",
+            user_id, tag, game_id
+        ).execute(&self.db).await?;
+        Ok(())
+    }
+
+    async fn get_pl_monsters_defeated(&self, user_id: i64) -> Result<i64> {
+        let monster_type = QuestKind::Monster as i64;
+        Ok(
+            sqlx::query!(
+                "SELECT COUNT(*) AS count FROM quests WHERE user_id = ? AND quest_type = ? AND succeeded = TRUE",
+                user_id, monster_type
+            ).fetch_one(&self.db).await?.count
+        )
+    }
+
+    async fn get_pl_battles_lost(&self, user_id: i64) -> Result<i64> {
+        let monster_type = QuestKind::Monster as i64;
+        Ok(
+            sqlx::query!(
+                "SELECT COUNT(*) AS count FROM quests WHERE user_id = ? AND quest_type = ? AND completed = TRUE AND succeeded = FALSE",
+                user_id, monster_type
+            ).fetch_one(&self.db).await?.count
+        )
+    }
+
+    async fn get_pl_riddles_solved(&self, user_id: i64) -> Result<i64> {
+        Ok(
+            sqlx::query!("
+                SELECT COUNT(DISTINCT qr.riddle_idx) AS count FROM quests q
+                JOIN quest_riddles qr ON qr.quest_id = q.id
+                WHERE q.user_id = ? AND q.succeeded = TRUE
+            ", user_id).fetch_one(&self.db).await?.count
+        )
+    }
+
+    async fn pl_lost_battle_in_game(&self, game_id: i64, user_id: i64) -> Result<bool> {
+        let monster_type = QuestKind::Monster as i64;
+        Ok(
+            sqlx::query!("
+                SELECT id FROM quests 
+                WHERE game_id = ? AND user_id = ? AND quest_type = ? AND completed = TRUE AND succeeded = FALSE
+            ", game_id, user_id, monster_type).fetch_optional(&self.db).await?.is_some()
+        )
+    }
+
+    async fn get_game_created_on(&self, game_id: i64) -> Result<NaiveDateTime> {
+        Ok(
+            sqlx::query!("SELECT created_on FROM game_states WHERE id = ?", game_id)
+                .fetch_one(&self.db).await?.created_on
+        )
+    }
+}
@@ -0,0 +1,26 @@
+use axum::{response::{IntoResponse, Response}, http::StatusCode};
+use log::error;
+use thiserror::Error;
+
+use crate::data_layer_error::DataLayerError;
+
+pub type Result<T> = std::result::Result<T, AchievementServiceError>;
+
+#[derive(Debug, Error)]
+pub enum AchievementServiceError {
+    #[error("An internal server error has occurred")]
+    DataLayerError(DataLayerError),
+}
+
+impl From<DataLayerError> for AchievementServiceError {
+    fn from(value: DataLayerError) -> Self {
+        AchievementServiceError::DataLayerError(value)
+    }
+}
+
+impl IntoResponse for AchievementServiceError {
+    fn into_response(self) -> Response {
+        error!("{:?}", self);
+        (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()).into_response()
+    }
+}
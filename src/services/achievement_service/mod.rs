@@ -0,0 +1,127 @@
+pub mod data_layer;
+pub mod error;
+pub mod models;
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use chrono::{Duration, Utc};
+use derive_more::Constructor;
+
+use crate::resources::game_resources::{Achievement, AchievementCondition, Resources};
+
+use self::{data_layer::AchievementDataLayer, error::Result, models::{AchievementModel, GameEvent}};
+
+///
+/// Service which unlocks achievements for players as they reach the
+/// milestones defined in the achievement resources. Achievements are account-wide - 
+/// they are unlocked once per user, and count progress across every game the user
+/// has played, except for the win conditions, which only consider the game won
+/// 
+#[async_trait]
+pub trait AchievementService : Send + Sync {
+    ///
+    /// Evaluates the achievements the `event` may unlock for the user with the given `user_id`,
+    /// unlocking those whose condition is met. Returns the newly unlocked achievements
+    /// 
+    async fn handle_event(&self, user_id: i64, event: GameEvent) -> Result<Vec<AchievementModel>>;
+    ///
+    /// Retrieves every achievement the user has unlocked, marking those newly unlocked as seen
+    /// 
+    async fn get_achievements(&self, user_id: i64) -> Result<Vec<AchievementModel>>;
+}
+
+#[derive(Constructor)]
+pub struct CoreAchievementService {
+    data_layer: Arc<dyn AchievementDataLayer>,
+    res: Arc<Resources>,
+}
+
+#[async_trait]
+impl AchievementService for CoreAchievementService {
+    async fn handle_event(&self, user_id: i64, event: GameEvent) -> Result<Vec<AchievementModel>> {
+        let unlocked = self.data_layer.get_achievements(user_id).await?;
+        let game_id = match event {
+            GameEvent::GameWon { game_id } => Some(game_id),
+            _ => None,
+        };
+
+        let mut newly_unlocked = Vec::new();
+        for achievement in &self.res.achievements {
+            if unlocked.iter().any(|entity| entity.tag == achievement.tag) || !triggered_by(&achievement.condition, &event) {
+                continue;
+            }
+            if !self.condition_met(user_id, &achievement.condition, &event).await? {
+                continue;
+            }
+
+            self.data_layer.unlock_achievement(user_id, &achievement.tag, game_id).await?;
+            newly_unlocked.push(achievement_model(achievement, Utc::now().naive_utc(), true));
+        }
+        Ok(newly_unlocked)
+    }
+
+    async fn get_achievements(&self, user_id: i64) -> Result<Vec<AchievementModel>> {
+        let unlocked = self.data_layer.get_achievements(user_id).await?;
+        self.data_layer.mark_achievements_seen(user_id).await?;
+
+        // Achievements no longer in the resources are left out
+        Ok(
+            unlocked.iter().filter_map(|entity| {
+                self.res.achievements.iter().find(|achievement| achievement.tag == entity.tag)
+                    .map(|achievement| achievement_model(achievement, entity.unlocked_on, !entity.seen))
+            }).collect()
+        )
+    }
+}
+
+impl CoreAchievementService {
+    ///
+    /// Checks if the user has met the `condition`, on the given `event`
+    /// 
+    async fn condition_met(&self, user_id: i64, condition: &AchievementCondition, event: &GameEvent) -> Result<bool> {
+        let dl = &self.data_layer;
+        Ok(match (condition, event) {
+            (AchievementCondition::MonstersDefeated { count }, _) => 
+                dl.get_pl_monsters_defeated(user_id).await? >= *count,
+            (AchievementCondition::BattlesLost { count }, _) => 
+                dl.get_pl_battles_lost(user_id).await? >= *count,
+            (AchievementCondition::RiddlesSolved { count }, _) => {
+                let count = count.unwrap_or(self.res.riddles.len() as i64);
+                dl.get_pl_riddles_solved(user_id).await? >= count
+            },
+            (AchievementCondition::WonGame, GameEvent::GameWon { .. }) => true,
+            (AchievementCondition::WonWithoutLosingBattle, GameEvent::GameWon { game_id }) => 
+                !dl.pl_lost_battle_in_game(*game_id, user_id).await?,
+            (AchievementCondition::WonWithinDays { days }, GameEvent::GameWon { game_id }) => {
+                let created_on = dl.get_game_created_on(*game_id).await?;
+                Utc::now().naive_utc() - created_on <= Duration::days(*days)
+            },
+            _ => false,
+        })
+    }
+}
+
+///
+/// Checks if the `event` could unlock an achievement with the given `condition`
+/// 
+fn triggered_by(condition: &AchievementCondition, event: &GameEvent) -> bool {
+    match condition {
+        AchievementCondition::MonstersDefeated { .. } => *event == GameEvent::MonsterDefeated,
+        AchievementCondition::BattlesLost { .. } => *event == GameEvent::BattleLost,
+        AchievementCondition::RiddlesSolved { .. } => *event == GameEvent::RiddleSolved,
+        AchievementCondition::WonGame 
+        | AchievementCondition::WonWithoutLosingBattle 
+        | AchievementCondition::WonWithinDays { .. } => matches!(event, GameEvent::GameWon { .. }),
+    }
+}
+
+fn achievement_model(achievement: &Achievement, unlocked_on: chrono::NaiveDateTime, new: bool) -> AchievementModel {
+    AchievementModel {
+        tag: achievement.tag.clone(),
+        name: achievement.name.clone(),
+        description: achievement.description.clone(),
+        unlocked_on,
+        new,
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+///
+/// Something a player has done, which may unlock achievements
+/// 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+    MonsterDefeated,
+    BattleLost,
+    RiddleSolved,
+    GameWon { game_id: i64 },
+}
+
+///
+/// An achievement the player has unlocked. `new` achievements have 
+/// been unlocked since the player last viewed their achievements
+/// 
+#[derive(Debug, Serialize)]
+pub struct AchievementModel {
+    pub tag: String,
+    pub name: String,
+    pub description: String,
+    pub unlocked_on: NaiveDateTime,
+    pub new: bool,
+}
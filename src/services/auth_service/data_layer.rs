@@ -1,24 +1,40 @@
 use axum::async_trait;
-use chrono::{Duration, Utc};
+use chrono::{Duration, NaiveDateTime, Utc};
 use derive_more::Constructor;
 use sqlx::SqlitePool;
 
-use crate::{data_layer_error::Result, services::token_service::settings::TokenSettings};
+use crate::{data_layer_error::Result, services::token_service::{models::Role, settings::TokenSettings}};
 
-use super::models::{UserModel, RefrTokenModel};
+use super::models::{LoginFailuresModel, PendingUserModel, RegistrationOutcome, UserModel, RefrTokenModel};
 
 #[async_trait]
 pub trait AuthDataLayer : Send + Sync {
-    async fn get_all_users(&self) -> Result<Vec<(String, i64)>>;
     async fn get_user_by_id(&self, user_id: i64) -> Result<Option<UserModel>>;
     async fn get_user_by_email<'a>(&self, email: &'a str) -> Result<Option<UserModel>>;
 
-    async fn get_refr_token_by_token<'a>(&self, token: &'a str) -> Result<Option<RefrTokenModel>>;
+    async fn get_refr_token_by_hash<'a>(&self, token_hash: &'a str) -> Result<Option<RefrTokenModel>>;
     async fn get_refr_token_by_id(&self, token: i64) -> Result<Option<RefrTokenModel>>;
-    async fn create_refr_token<'a>(&self, user_id: i64, token: &'a str) -> Result<i64>;
+    async fn get_active_refr_tokens(&self, user_id: i64) -> Result<Vec<RefrTokenModel>>;
+    async fn create_refr_token<'a>(&self, user_id: i64, token_hash: &'a str, user_agent: Option<&'a str>, session_started_on: Option<NaiveDateTime>) -> Result<i64>;
     async fn revoke_refr_token<'a>(&self, id: i64, repl_id: Option<i64>, revoked_by: &'a str) -> Result<()>;
+    async fn revoke_all_refr_tokens<'a>(&self, user_id: i64, revoked_by: &'a str) -> Result<()>;
 
-    async fn create_user<'a>(&self, email: &'a str, pwd_hash: &'a str, card_idx: i64) -> Result<()>;
+    async fn create_user<'a>(&self, email: &'a str, pwd_hash: &'a str, card_idx: i64, role: Role) -> Result<i64>;
+    async fn register_user<'a>(&self, email: &'a str, pwd_hash: &'a str, card_idx: i64, invite_code: Option<&'a str>) -> Result<RegistrationOutcome>;
+    async fn is_card_idx_claimed(&self, card_idx: i64) -> Result<bool>;
+    async fn get_pending_users(&self) -> Result<Vec<PendingUserModel>>;
+    async fn approve_user(&self, user_id: i64) -> Result<bool>;
+    async fn update_pwd_hash<'a>(&self, user_id: i64, pwd_hash: &'a str) -> Result<()>;
+    async fn update_role(&self, user_id: i64, role: Role) -> Result<bool>;
+    async fn has_admin(&self) -> Result<bool>;
+    async fn create_pwd_reset_token<'a>(&self, user_id: i64, token_hash: &'a str, lifetime_s: i64) -> Result<()>;
+    async fn redeem_pwd_reset_token<'a>(&self, token_hash: &'a str, pwd_hash: &'a str) -> Result<Option<i64>>;
+
+    async fn create_invite_code<'a>(&self, code: &'a str) -> Result<()>;
+
+    async fn record_login_attempt<'a>(&self, email: &'a str, user_id: Option<i64>, ip: Option<&'a str>, succeeded: bool) -> Result<()>;
+    async fn get_account_login_failures<'a>(&self, email: &'a str, since: NaiveDateTime) -> Result<LoginFailuresModel>;
+    async fn get_ip_login_failures<'a>(&self, ip: &'a str, since: NaiveDateTime) -> Result<LoginFailuresModel>;
 }
 
 #[derive(Constructor)]
@@ -29,49 +45,58 @@ pub struct DbAuthDataLayer {
 
 #[async_trait]
 impl AuthDataLayer for DbAuthDataLayer {
-    async fn get_all_users(&self) -> Result<Vec<(String, i64)>> {
-        Ok(sqlx::query!("SELECT id, email FROM users").fetch_all(&self.db).await?.into_iter().map(|u| (u.email, u.id)).collect())
-    }
     async fn get_user_by_id(&self, user_id: i64) -> Result<Option<UserModel>> {
         let user = sqlx::query_as!(UserModel, 
-            "SELECT id, email, pwd_hash FROM users WHERE id = ?", user_id
+            r#"SELECT id, email, pwd_hash, approved, role as "role: Role" FROM users WHERE id = ?"#, user_id
         ).fetch_optional(&self.db).await?;
 
         Ok(user)
     }
     async fn get_user_by_email<'a>(&self, email: &'a str) -> Result<Option<UserModel>> {
         let user = sqlx::query_as!(UserModel,
-            "SELECT id, email, pwd_hash FROM users WHERE email = ?", email
+            r#"SELECT id, email, pwd_hash, approved, role as "role: Role" FROM users WHERE email = ?"#, email
         ).fetch_optional(&self.db).await?;
 
         Ok(user)
     }
-    async fn get_refr_token_by_token<'a>(&self, token: &'a str) -> Result<Option<RefrTokenModel>> {
+    async fn get_refr_token_by_hash<'a>(&self, token_hash: &'a str) -> Result<Option<RefrTokenModel>> {
         let refr_token = sqlx::query_as!(RefrTokenModel, "
-            SELECT id, user_id, token, repl_id, revoked_on 
-            FROM refresh_tokens WHERE token = ?
-            ", token
+            SELECT id, user_id, token_hash, repl_id, created_on, session_started_on, user_agent, expires, revoked_on 
+            FROM refresh_tokens WHERE token_hash = ?
+            ", token_hash
         ).fetch_optional(&self.db).await?;
 
         Ok(refr_token)
     }
     async fn get_refr_token_by_id(&self, id: i64) -> Result<Option<RefrTokenModel>> {
         let refr_token = sqlx::query_as!(RefrTokenModel, "
-            SELECT id, user_id, token, repl_id, revoked_on
+            SELECT id, user_id, token_hash, repl_id, created_on, session_started_on, user_agent, expires, revoked_on
             FROM refresh_tokens WHERE id = ?
             ", id
         ).fetch_optional(&self.db).await?;
 
         Ok(refr_token)
     }
-    async fn create_refr_token<'a>(&self, user_id: i64, token: &'a str) -> Result<i64> {
+    async fn get_active_refr_tokens(&self, user_id: i64) -> Result<Vec<RefrTokenModel>> {
+        let now = Utc::now();
+        let refr_tokens = sqlx::query_as!(RefrTokenModel, "
+            SELECT id, user_id, token_hash, repl_id, created_on, session_started_on, user_agent, expires, revoked_on
+            FROM refresh_tokens WHERE user_id = ? AND revoked_on IS NULL AND (expires IS NULL OR expires > ?)
+            ORDER BY created_on DESC
+            ", user_id, now
+        ).fetch_all(&self.db).await?;
+
+        Ok(refr_tokens)
+    }
+    async fn create_refr_token<'a>(&self, user_id: i64, token_hash: &'a str, user_agent: Option<&'a str>, session_started_on: Option<NaiveDateTime>) -> Result<i64> {
         let now = Utc::now();
         let expires = now + Duration::seconds(self.settings.refr_token_lifetime_s);
+        let session_started_on = session_started_on.unwrap_or(now.naive_utc());
 
         let res = sqlx::query!("
-            INSERT INTO refresh_tokens (user_id, expires, token)
-            VALUES (?, ?, ?)
-            ", user_id, expires, token
+            INSERT INTO refresh_tokens (user_id, expires, token_hash, user_agent, session_started_on)
+            VALUES (?, ?, ?, ?, ?)
+            ", user_id, expires, token_hash, user_agent, session_started_on
         ).execute(&self.db).await?;
 
         Ok(res.last_insert_rowid())
@@ -85,11 +110,175 @@ impl AuthDataLayer for DbAuthDataLayer {
         ).execute(&self.db).await?;
         Ok(())
     }
-    async fn create_user<'a>(&self, email: &'a str, pwd_hash: &'a str, card_idx: i64) -> Result<()> {
+    async fn revoke_all_refr_tokens<'a>(&self, user_id: i64, revoked_by: &'a str) -> Result<()> {
+        let now = Utc::now().fixed_offset();
         sqlx::query!("
-            INSERT INTO users (email, pwd_hash, card_idx) VALUES (?, ?, ?)
-            ", email, pwd_hash, card_idx
+            UPDATE refresh_tokens SET revoked_on = ?, revoked_by = ?
+            WHERE user_id = ? AND revoked_on IS NULL
+            ", now, revoked_by, user_id
         ).execute(&self.db).await?;
         Ok(())
     }
-}
\ No newline at end of file
+    async fn create_user<'a>(&self, email: &'a str, pwd_hash: &'a str, card_idx: i64, role: Role) -> Result<i64> {
+        Ok(
+            sqlx::query!("
+                INSERT INTO users (email, pwd_hash, card_idx, role) VALUES (?, ?, ?, ?)
+                ", email, pwd_hash, card_idx, role
+            ).execute(&self.db).await?.last_insert_rowid()
+        )
+    }
+    async fn register_user<'a>(&self, email: &'a str, pwd_hash: &'a str, card_idx: i64, invite_code: Option<&'a str>) -> Result<RegistrationOutcome> {
+        let mut tx = self.db.begin().await?;
+        let now = Utc::now().naive_utc();
+
+        // Claim the invite code, if any, so it can't be used twice
+        if let Some(code) = invite_code {
+            let claimed = sqlx::query!(
+                "UPDATE invite_codes SET used_on = ? WHERE code = ? AND used_on IS NULL", now, code
+            ).execute(&mut *tx).await?.rows_affected() == 1;
+            if !claimed {
+                return Ok(RegistrationOutcome::InvalidInviteCode);
+            }
+        }
+
+        // Users registering with an invite code are approved immediately
+        let approved = invite_code.is_some();
+        let inserted = sqlx::query!("
+            INSERT INTO users (email, pwd_hash, card_idx, approved) VALUES (?, ?, ?, ?)
+            ", email, pwd_hash, card_idx, approved
+        ).execute(&mut *tx).await;
+
+        // A concurrent registration may have taken the email or avatar since it was validated
+        let user_id = match inserted {
+            Ok(res) => res.last_insert_rowid(),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                return Ok(if e.message().contains("users.card_idx") {
+                    RegistrationOutcome::AvatarTaken
+                } else {
+                    RegistrationOutcome::EmailTaken
+                });
+            },
+            Err(e) => return Err(e.into()),
+        };
+
+        if let Some(code) = invite_code {
+            sqlx::query!("UPDATE invite_codes SET used_by = ? WHERE code = ?", user_id, code)
+                .execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+        Ok(RegistrationOutcome::Registered(user_id))
+    }
+    async fn is_card_idx_claimed(&self, card_idx: i64) -> Result<bool> {
+        Ok(
+            sqlx::query!("SELECT id FROM users WHERE card_idx = ?", card_idx)
+                .fetch_optional(&self.db).await?.is_some()
+        )
+    }
+    async fn get_pending_users(&self) -> Result<Vec<PendingUserModel>> {
+        Ok(
+            sqlx::query_as!(PendingUserModel,
+                "SELECT id, email, card_idx FROM users WHERE approved = FALSE ORDER BY id ASC"
+            ).fetch_all(&self.db).await?
+        )
+    }
+    async fn approve_user(&self, user_id: i64) -> Result<bool> {
+        Ok(
+            sqlx::query!("UPDATE users SET approved = TRUE WHERE id = ? AND approved = FALSE", user_id)
+                .execute(&self.db).await?.rows_affected() == 1
+        )
+    }
+    async fn update_pwd_hash<'a>(&self, user_id: i64, pwd_hash: &'a str) -> Result<()> {
+        sqlx::query!("UPDATE users SET pwd_hash = ? WHERE id = ?", pwd_hash, user_id)
+            .execute(&self.db).await?;
+        Ok(())
+    }
+    async fn update_role(&self, user_id: i64, role: Role) -> Result<bool> {
+        Ok(
+            sqlx::query!("UPDATE users SET role = ? WHERE id = ?", role, user_id)
+                .execute(&self.db).await?.rows_affected() == 1
+        )
+    }
+    async fn has_admin(&self) -> Result<bool> {
+        let admin = Role::Admin;
+        Ok(
+            sqlx::query!("SELECT id FROM users WHERE role = ? LIMIT 1", admin)
+                .fetch_optional(&self.db).await?.is_some()
+        )
+    }
+    async fn create_pwd_reset_token<'a>(&self, user_id: i64, token_hash: &'a str, lifetime_s: i64) -> Result<()> {
+        let expires = Utc::now() + Duration::seconds(lifetime_s);
+        let mut tx = self.db.begin().await?;
+
+        // Only the latest reset token issued to the user can be redeemed
+        sqlx::query!("DELETE FROM password_reset_tokens WHERE user_id = ? AND used_on IS NULL", user_id)
+            .execute(&mut *tx).await?;
+        sqlx::query!("
+            INSERT INTO password_reset_tokens (user_id, token_hash, expires) VALUES (?, ?, ?)
+            ", user_id, token_hash, expires
+        ).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+    async fn redeem_pwd_reset_token<'a>(&self, token_hash: &'a str, pwd_hash: &'a str) -> Result<Option<i64>> {
+        let now = Utc::now();
+        let mut tx = self.db.begin().await?;
+
+        let Some(token) = sqlx::query!("
+            UPDATE password_reset_tokens SET used_on = ?
+            WHERE token_hash = ? AND used_on IS NULL AND expires > ?
+            RETURNING user_id
+            ", now, token_hash, now
+        ).fetch_optional(&mut *tx).await? else {
+            return Ok(None);
+        };
+        sqlx::query!("UPDATE users SET pwd_hash = ? WHERE id = ?", pwd_hash, token.user_id)
+            .execute(&mut *tx).await?;
+
+        tx.commit().await?;
+        Ok(Some(token.user_id))
+    }
+    async fn create_invite_code<'a>(&self, code: &'a str) -> Result<()> {
+        sqlx::query!("INSERT INTO invite_codes (code) VALUES (?)", code)
+            .execute(&self.db).await?;
+        Ok(())
+    }
+    async fn record_login_attempt<'a>(&self, email: &'a str, user_id: Option<i64>, ip: Option<&'a str>, succeeded: bool) -> Result<()> {
+        let now = Utc::now().naive_utc();
+        sqlx::query!("
+            INSERT INTO login_attempts (email, user_id, ip, attempted_on, succeeded) VALUES (?, ?, ?, ?, ?)
+            ", email, user_id, ip, now, succeeded
+        ).execute(&self.db).await?;
+        Ok(())
+    }
+    async fn get_account_login_failures<'a>(&self, email: &'a str, since: NaiveDateTime) -> Result<LoginFailuresModel> {
+        // Only the failures made since the last successful login count against the account
+        Ok(
+            sqlx::query_as!(LoginFailuresModel, r#"
+                SELECT
+                    COUNT(*) as "count!: i64",
+                    MIN(attempted_on) as "first_attempted_on: NaiveDateTime",
+                    MAX(attempted_on) as "last_attempted_on: NaiveDateTime"
+                FROM login_attempts
+                WHERE email = ? AND succeeded = FALSE AND attempted_on > ? AND attempted_on > COALESCE(
+                    (SELECT MAX(attempted_on) FROM login_attempts WHERE email = ? AND succeeded = TRUE), 0
+                )
+                "#, email, since, email
+            ).fetch_one(&self.db).await?
+        )
+    }
+    async fn get_ip_login_failures<'a>(&self, ip: &'a str, since: NaiveDateTime) -> Result<LoginFailuresModel> {
+        Ok(
+            sqlx::query_as!(LoginFailuresModel, r#"
+                SELECT
+                    COUNT(*) as "count!: i64",
+                    MIN(attempted_on) as "first_attempted_on: NaiveDateTime",
+                    MAX(attempted_on) as "last_attempted_on: NaiveDateTime"
+                FROM login_attempts
+                WHERE ip = ? AND succeeded = FALSE AND attempted_on > ?
+                "#, ip, since
+            ).fetch_one(&self.db).await?
+        )
+    }
+}
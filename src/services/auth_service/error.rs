@@ -1,4 +1,4 @@
-use axum::{response::{IntoResponse, Response}, http::StatusCode};
+use axum::{response::{IntoResponse, Response}, http::{header::RETRY_AFTER, StatusCode}};
 use log::error;
 use thiserror::Error;
 
@@ -10,12 +10,18 @@ pub type Result<T> = std::result::Result<T, AuthServiceError>;
 pub enum AuthServiceError {
     #[error("Refresh token cookie not found")]
     CookieNotFound,
+    #[error("CSRF token is missing or does not match")]
+    CsrfTokenMismatch,
     #[error("An internal server error has occurred")]
     DataLayerError(DataLayerError),
-    #[error("The given email {0} doesn't exist")]
-    EmailDoesNotExist(String),
-    #[error("Password does not match for email {0}")]
-    PasswordDoesNotMatch(String),
+    #[error("An internal server error has occurred")]
+    PasswordHashError(argon2::Error),
+    #[error("Invalid email or password")]
+    InvalidCredentials,
+    #[error("Too many failed login attempts. Try again in {0} seconds")]
+    TooManyLoginAttempts(i64),
+    #[error("The current password does not match")]
+    PasswordDoesNotMatch,
     #[error("Refresh token duplicate usage. duplicate ID `{dup_id}`, revoked ID `{revoked_id}`, user ID `{user_id}`")]
     DuplicateRefresh { user_id: i64, dup_id: i64, revoked_id: i64 },
     #[error("The token provided doesn't exist")]
@@ -24,6 +30,34 @@ pub enum AuthServiceError {
     UserNotFound(i64, i64),
     #[error("An error has occured")]
     TokenServiceError(token_service::error::TokenError),
+    #[error("The account for email {0} is awaiting approval")]
+    AccountNotApproved(String),
+    #[error("'{0}' is not a valid email address")]
+    InvalidEmail(String),
+    #[error("Password must be at least 8 characters long, with both letters and digits")]
+    WeakPassword,
+    #[error("No avatar exists for card index {0}")]
+    AvatarNotFound(i64),
+    #[error("The avatar for card index {0} has already been claimed")]
+    AvatarAlreadyClaimed(i64),
+    #[error("The email {0} is already registered")]
+    EmailAlreadyRegistered(String),
+    #[error("The invite code is invalid or has already been used")]
+    InvalidInviteCode,
+    #[error("User {0} is not awaiting approval")]
+    UserNotPending(i64),
+    #[error("User {0} doesn't exist")]
+    UserDoesNotExist(i64),
+    #[error("The password reset token is invalid, used or expired")]
+    InvalidResetToken,
+    #[error("Session {0} not found")]
+    SessionNotFound(i64),
+    #[error("Refresh token {0} has expired. Please sign in again")]
+    RefreshTokenExpired(i64),
+    #[error("Admins cannot change their own role")]
+    CannotChangeOwnRole,
+    #[error("An admin already exists")]
+    AdminAlreadyExists,
 }
 
 impl From<DataLayerError> for AuthServiceError {
@@ -41,8 +75,20 @@ impl From<TokenError> for AuthServiceError {
 impl IntoResponse for AuthServiceError {
     fn into_response(self) -> Response {
         println!("{:?}", self);
-        return if let AuthServiceError::DataLayerError(e) = &self {
+        if let AuthServiceError::TokenServiceError(e) = self {
+            return e.into_response();
+        }
+        if let AuthServiceError::TooManyLoginAttempts(retry_after_s) = &self {
+            return (StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, retry_after_s.to_string())], self.to_string()).into_response();
+        }
+        return if let AuthServiceError::DataLayerError(_) | AuthServiceError::PasswordHashError(_) = &self {
             (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()).into_response()
+        } else if let AuthServiceError::AvatarAlreadyClaimed(_) | AuthServiceError::EmailAlreadyRegistered(_) | AuthServiceError::AdminAlreadyExists = &self {
+            (StatusCode::CONFLICT, self.to_string()).into_response()
+        } else if let AuthServiceError::RefreshTokenExpired(_) | AuthServiceError::InvalidCredentials = &self {
+            (StatusCode::UNAUTHORIZED, self.to_string()).into_response()
+        } else if let AuthServiceError::AccountNotApproved(_) | AuthServiceError::CsrfTokenMismatch = &self {
+            (StatusCode::FORBIDDEN, self.to_string()).into_response()
         } else {
             (StatusCode::BAD_REQUEST, self.to_string()).into_response()
         }
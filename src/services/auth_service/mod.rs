@@ -1,87 +1,129 @@
 pub mod error;
 pub mod data_layer;
 pub mod models;
+pub mod settings;
 
 use std::sync::Arc;
 
-use argon2::Config;
 use axum::async_trait;
+use chrono::{Duration, NaiveDateTime, Utc};
+use base64::{engine::general_purpose, Engine};
 use derive_more::Constructor;
 use dotenvy::dotenv;
 use dotenv_codegen::dotenv;
 use lazy_static::lazy_static;
+use log::warn;
+use rand::{distributions::Alphanumeric, rngs::OsRng, thread_rng, Rng, RngCore};
+use regex::Regex;
 
-use crate::data_layer_error;
+use crate::{data_layer_error, resources::game_resources::Resources};
 
-use self::{error::{Result, AuthServiceError}, data_layer::AuthDataLayer, models::RefrTokenModel};
+use self::{error::{Result, AuthServiceError}, data_layer::AuthDataLayer, models::{LoginFailuresModel, PendingUserModel, RefrTokenModel, RegisteredUserModel, RegistrationOutcome, SessionModel}, settings::AuthSettings};
 
-use super::token_service::{TokenService, models::AuthTokensModel};
+use super::{game_service::PERSON_CAT_IDX, token_service::{digest_token, TokenService, models::{AuthTokensModel, Role}}};
+
+const MIN_PWD_LENGTH: usize = 8;
+const INVITE_CODE_LENGTH: usize = 16;
+const RESET_TOKEN_LENGTH: usize = 32;
 
 lazy_static! {
+    ///
+    /// The salt every password used to be hashed with. Only used to 
+    /// recognize legacy hashes, which are rehashed on login
+    /// 
     static ref SALT: String = {
         dotenv().ok().expect(".env file must be provided");
         dotenv!("SALT").to_string()
     };
+    static ref EMAIL_REGEX: Regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
 }
 
 #[async_trait]
 pub trait AuthService: Send + Sync {
-    async fn print_all_access_tokens(&self) -> Result<()>;
     async fn try_accept_access_token(&self, access_token: &str) -> Result<AuthTokensModel>;
-    async fn try_accept_creds(&self, email: String, pwd: String) -> Result<AuthTokensModel>;
+    async fn try_accept_creds(&self, email: String, pwd: String, user_agent: Option<String>, ip: Option<String>) -> Result<AuthTokensModel>;
     async fn try_accept_refresh(&self, refr_token: String) -> Result<AuthTokensModel>;
-    async fn create_new_user(&self, email: String, pwd: String, card_idx: usize) -> Result<()>;
+    async fn prepare_new_user<'a>(&self, email: &'a str, pwd: &'a str, card_idx: i64) -> Result<String>;
+    async fn register(&self, email: String, pwd: String, card_idx: i64, invite_code: Option<String>) -> Result<RegisteredUserModel>;
+    async fn create_invite_code(&self) -> Result<String>;
+    async fn get_pending_users(&self) -> Result<Vec<PendingUserModel>>;
+    async fn approve_user(&self, user_id: i64) -> Result<()>;
+    async fn change_password(&self, user_id: i64, old_pwd: String, new_pwd: String) -> Result<()>;
+    async fn create_pwd_reset_token(&self, user_id: i64) -> Result<String>;
+    async fn reset_password(&self, token: String, new_pwd: String) -> Result<()>;
+    async fn logout(&self, refr_token: String, access_token: Option<String>) -> Result<()>;
+    async fn get_sessions(&self, user_id: i64, refr_token: Option<String>) -> Result<Vec<SessionModel>>;
+    async fn revoke_session(&self, user_id: i64, session_id: i64) -> Result<()>;
+    async fn set_role(&self, admin_id: i64, user_id: i64, role: Role) -> Result<()>;
+    async fn bootstrap_admin(&self, email: String, pwd: String, card_idx: i64) -> Result<i64>;
 }
 
 #[derive(Clone, Constructor)]
 pub struct CoreAuthService {
     data_layer: Arc<dyn AuthDataLayer>,
     token_service: Arc<dyn TokenService>,
+    res: Arc<Resources>,
+    settings: AuthSettings,
 }
 
 #[async_trait]
 impl AuthService for CoreAuthService {
-    async fn print_all_access_tokens(&self) -> Result<()> {
-        let users = self.data_layer.get_all_users().await?;
-        for (email, id) in users {
-            let access_token = self.token_service.generate_auth_tokens(id).access_token;
-            println!("User {email} ({id}): {access_token}");
-        }
-
-        Ok(())
-    }
     async fn try_accept_access_token(&self, access_token: &str) -> Result<AuthTokensModel> {
-        let user_id = self.token_service.verify_access_token(access_token)?;
-        let tokens = self.token_service.generate_auth_tokens(user_id);
+        let claims = self.token_service.verify_access_token(access_token).await?;
+
+        // Issue the new token with the user's current role, which may have changed
+        let user = self.data_layer.get_user_by_id(claims.user_id).await?
+            .ok_or(AuthServiceError::UserDoesNotExist(claims.user_id))?;
+        let tokens = self.token_service.generate_auth_tokens(user.id, user.role)?;
 
         Ok(tokens)
     }
-    async fn try_accept_creds(&self, email: String, pwd: String) -> Result<AuthTokensModel> {
+    async fn try_accept_creds(&self, email: String, pwd: String, user_agent: Option<String>, ip: Option<String>) -> Result<AuthTokensModel> {
+        // Refuse to check the password while the account or IP address is being throttled
+        self.check_login_throttle(&email, ip.as_deref()).await?;
+
         // Get the user associated with the email (if exists)
         let user = self.data_layer.get_user_by_email(&email).await?;
 
-        if let Some(user) = user {
-            // Verify that the password given matches the user's
-            let matches = argon2::verify_encoded(&user.pwd_hash, pwd.as_bytes()).unwrap();
+        // Verify that the password given matches the user's. Unknown emails still
+        // cost a hash, so they can't be told apart by the response time
+        let matches = match &user {
+            Some(user) => argon2::verify_encoded(&user.pwd_hash, pwd.as_bytes())
+                .map_err(AuthServiceError::PasswordHashError)?,
+            None => {
+                hash_pwd(&pwd, &self.settings)?;
+                false
+            }
+        };
+
+        let user_id = user.as_ref().map(|user| user.id);
+        self.data_layer.record_login_attempt(&email, user_id, ip.as_deref(), matches).await?;
 
-            // If matches, add the new refresh token and return the tokens
-            if matches {
-                let tokens = self.token_service.generate_auth_tokens(user.id);
-                self.data_layer.create_refr_token(user.id, &tokens.refresh_token)
-                    .await.map_err(|e| AuthServiceError::DataLayerError(e))?;
-                
-                return Ok(tokens);
-            } else {
-                return Err(AuthServiceError::PasswordDoesNotMatch(user.email))
-            };
+        let Some(user) = user.filter(|_| matches) else {
+            return Err(AuthServiceError::InvalidCredentials);
+        };
+
+        // If matches, add the new refresh token and return the tokens
+        if !user.approved {
+            return Err(AuthServiceError::AccountNotApproved(user.email));
+        }
+
+        // Rehash legacy hashes, or those made with outdated parameters, now the password is known
+        if needs_rehash(&user.pwd_hash, &self.settings) {
+            let pwd_hash = hash_pwd(&pwd, &self.settings)?;
+            self.data_layer.update_pwd_hash(user.id, &pwd_hash).await?;
         }
 
-        Err(AuthServiceError::EmailDoesNotExist(email))
+        let tokens = self.token_service.generate_auth_tokens(user.id, user.role)?;
+        self.data_layer.create_refr_token(user.id, &digest_token(&tokens.refresh_token), user_agent.as_deref(), None)
+            .await.map_err(|e| AuthServiceError::DataLayerError(e))?;
+
+        Ok(tokens)
     }
 
     async fn try_accept_refresh(&self, token: String) -> Result<AuthTokensModel> {
         // Attempt to query the refresh token that matches the token given
-        let refr_token = self.data_layer.get_refr_token_by_token(&token).await
+        let refr_token = self.data_layer.get_refr_token_by_hash(&digest_token(&token)).await
             .map_err(|e| AuthServiceError::DataLayerError(e))?;
 
         // Ensure the refresh token in question exists
@@ -90,7 +132,7 @@ impl AuthService for CoreAuthService {
             if refr_token.revoked_on.is_some() {
                 // If it has, revoke it's descendent refresh token,
                 // and return an error
-                let revoked_id = revoke_token(refr_token.clone(), &self.data_layer).await?;
+                let revoked_id = revoke_token(refr_token.clone(), &self.data_layer, "SERVER (DUPL. USAGE)").await?;
 
                 let error = Err(
                     AuthServiceError::DuplicateRefresh { 
@@ -102,17 +144,23 @@ impl AuthService for CoreAuthService {
                 return error;
             }
 
+            // Ensure the refresh token hasn't expired
+            if refr_token.expires.is_some_and(|expires| expires <= Utc::now().naive_utc()) {
+                return Err(AuthServiceError::RefreshTokenExpired(refr_token.id));
+            }
+
             // Get the user associated with the refresh token
             let user = self.data_layer.get_user_by_id(refr_token.user_id).await?;
                 
 
             if let Some(user) = user {
                 // Generate a new access and refresh token
-                let tokens = self.token_service.generate_auth_tokens(user.id);
+                let tokens = self.token_service.generate_auth_tokens(user.id, user.role)?;
 
-                // Add the new refresh token to the db
-                let repl_id = self.data_layer.create_refr_token(user.id, &tokens.refresh_token)
-                    .await?;
+                // Add the new refresh token to the db, continuing the session of the old one
+                let repl_id = self.data_layer.create_refr_token(
+                    user.id, &digest_token(&tokens.refresh_token), refr_token.user_agent.as_deref(), Some(refr_token.session_started_on)
+                ).await?;
 
                 // Update the old token's replacement to this one
                 self.data_layer.revoke_refr_token(refr_token.id, Some(repl_id), "CLIENT")
@@ -126,15 +174,265 @@ impl AuthService for CoreAuthService {
         return Err(AuthServiceError::TokenDoesNotExist);
     }
     
-    async fn create_new_user(&self, email: String, pwd: String, card_idx: usize) -> Result<()> {
-        let pwd_hash = argon2::hash_encoded(pwd.as_bytes(), SALT.as_bytes(), &Config::default()).unwrap();
-        self.data_layer.create_user(&email, &pwd_hash, card_idx as i64).await?;
+    async fn prepare_new_user<'a>(&self, email: &'a str, pwd: &'a str, card_idx: i64) -> Result<String> {
+        self.validate_new_user(email, pwd, card_idx).await?;
+
+        hash_pwd(pwd, &self.settings)
+    }
+
+    async fn register(&self, email: String, pwd: String, card_idx: i64, invite_code: Option<String>) -> Result<RegisteredUserModel> {
+        self.validate_new_user(&email, &pwd, card_idx).await?;
+
+        let pwd_hash = hash_pwd(&pwd, &self.settings)?;
+        let user_id = match self.data_layer.register_user(&email, &pwd_hash, card_idx, invite_code.as_deref()).await? {
+            RegistrationOutcome::Registered(user_id) => user_id,
+            RegistrationOutcome::InvalidInviteCode => return Err(AuthServiceError::InvalidInviteCode),
+            RegistrationOutcome::EmailTaken => return Err(AuthServiceError::EmailAlreadyRegistered(email)),
+            RegistrationOutcome::AvatarTaken => return Err(AuthServiceError::AvatarAlreadyClaimed(card_idx)),
+        };
+
+        Ok(RegisteredUserModel { user_id, approved: invite_code.is_some() })
+    }
+
+    async fn create_invite_code(&self) -> Result<String> {
+        let code: String = thread_rng().sample_iter(&Alphanumeric).take(INVITE_CODE_LENGTH).map(char::from).collect();
+        self.data_layer.create_invite_code(&code).await?;
+
+        Ok(code)
+    }
+
+    async fn get_pending_users(&self) -> Result<Vec<PendingUserModel>> {
+        Ok(self.data_layer.get_pending_users().await?)
+    }
+
+    async fn approve_user(&self, user_id: i64) -> Result<()> {
+        if !self.data_layer.approve_user(user_id).await? {
+            return Err(AuthServiceError::UserNotPending(user_id));
+        }
+        Ok(())
+    }
+
+    async fn change_password(&self, user_id: i64, old_pwd: String, new_pwd: String) -> Result<()> {
+        let user = self.data_layer.get_user_by_id(user_id).await?
+            .ok_or(AuthServiceError::UserDoesNotExist(user_id))?;
+        let matches = argon2::verify_encoded(&user.pwd_hash, old_pwd.as_bytes())
+            .map_err(AuthServiceError::PasswordHashError)?;
+        if !matches {
+            return Err(AuthServiceError::PasswordDoesNotMatch);
+        }
+        if !is_strong_pwd(&new_pwd) {
+            return Err(AuthServiceError::WeakPassword);
+        }
+
+        let pwd_hash = hash_pwd(&new_pwd, &self.settings)?;
+        self.data_layer.update_pwd_hash(user_id, &pwd_hash).await?;
+
+        // Sign out every session, so only the new password grants access
+        self.data_layer.revoke_all_refr_tokens(user_id, "SERVER (PWD. CHANGE)").await?;
+        Ok(())
+    }
+
+    async fn create_pwd_reset_token(&self, user_id: i64) -> Result<String> {
+        if self.data_layer.get_user_by_id(user_id).await?.is_none() {
+            return Err(AuthServiceError::UserDoesNotExist(user_id));
+        }
+
+        // Only the token's digest is stored, so a leaked database can't reset passwords
+        let token: String = OsRng.sample_iter(&Alphanumeric).take(RESET_TOKEN_LENGTH).map(char::from).collect();
+        self.data_layer.create_pwd_reset_token(user_id, &digest_token(&token), self.settings.reset_token_lifetime_s).await?;
+
+        Ok(token)
+    }
+
+    async fn reset_password(&self, token: String, new_pwd: String) -> Result<()> {
+        if !is_strong_pwd(&new_pwd) {
+            return Err(AuthServiceError::WeakPassword);
+        }
+
+        let pwd_hash = hash_pwd(&new_pwd, &self.settings)?;
+        let user_id = self.data_layer.redeem_pwd_reset_token(&digest_token(&token), &pwd_hash).await?
+            .ok_or(AuthServiceError::InvalidResetToken)?;
+
+        self.data_layer.revoke_all_refr_tokens(user_id, "SERVER (PWD. RESET)").await?;
+        Ok(())
+    }
+
+    async fn logout(&self, refr_token: String, access_token: Option<String>) -> Result<()> {
+        let refr_token = self.data_layer.get_refr_token_by_hash(&digest_token(&refr_token)).await?
+            .ok_or(AuthServiceError::TokenDoesNotExist)?;
+
+        // Revoke the latest token of the chain, ending the session
+        revoke_token(refr_token, &self.data_layer, "CLIENT (LOGOUT)").await?;
+
+        // Deny the access token still held by the client, so it can't outlive the session
+        if let Some(access_token) = access_token {
+            self.token_service.revoke_access_token(&access_token).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_sessions(&self, user_id: i64, refr_token: Option<String>) -> Result<Vec<SessionModel>> {
+        let refr_tokens = self.data_layer.get_active_refr_tokens(user_id).await?;
+        let current_hash = refr_token.map(|refr_token| digest_token(&refr_token));
+
+        Ok(
+            refr_tokens.into_iter().map(|token| SessionModel {
+                id: token.id,
+                started_on: token.session_started_on,
+                last_refreshed_on: token.created_on,
+                current: current_hash.is_some() && current_hash == token.token_hash,
+                user_agent: token.user_agent,
+            }).collect()
+        )
+    }
+
+    async fn revoke_session(&self, user_id: i64, session_id: i64) -> Result<()> {
+        let refr_token = self.data_layer.get_refr_token_by_id(session_id).await?
+            .filter(|token| token.user_id == user_id && token.revoked_on.is_none())
+            .ok_or(AuthServiceError::SessionNotFound(session_id))?;
+
+        self.data_layer.revoke_refr_token(refr_token.id, None, "CLIENT (SESSION REVOKED)").await?;
+        Ok(())
+    }
 
+    async fn set_role(&self, admin_id: i64, user_id: i64, role: Role) -> Result<()> {
+        // Admins can't demote themselves, so there is always an admin left
+        if admin_id == user_id {
+            return Err(AuthServiceError::CannotChangeOwnRole);
+        }
+        if !self.data_layer.update_role(user_id, role).await? {
+            return Err(AuthServiceError::UserDoesNotExist(user_id));
+        }
         Ok(())
     }
+
+    async fn bootstrap_admin(&self, email: String, pwd: String, card_idx: i64) -> Result<i64> {
+        // Further admins are promoted by an existing admin instead
+        if self.data_layer.has_admin().await? {
+            return Err(AuthServiceError::AdminAlreadyExists);
+        }
+        self.validate_new_user(&email, &pwd, card_idx).await?;
+
+        let pwd_hash = hash_pwd(&pwd, &self.settings)?;
+        let user_id = self.data_layer.create_user(&email, &pwd_hash, card_idx, Role::Admin).await?;
+
+        Ok(user_id)
+    }
 }
 
-async fn revoke_token(refr_token: RefrTokenModel, data_layer: &Arc<dyn AuthDataLayer>) -> data_layer_error::Result<i64> {
+impl CoreAuthService {
+    ///
+    /// Ensures a new user has a valid email and strong password, and registers as one
+    /// of the person cards, which must not already be claimed
+    /// 
+    async fn validate_new_user(&self, email: &str, pwd: &str, card_idx: i64) -> Result<()> {
+        if !EMAIL_REGEX.is_match(email) {
+            return Err(AuthServiceError::InvalidEmail(email.to_string()));
+        }
+        if !is_strong_pwd(pwd) {
+            return Err(AuthServiceError::WeakPassword);
+        }
+
+        let person_count = self.res.evd_cats_and_cards[PERSON_CAT_IDX].cards.len() as i64;
+        if !(0..person_count).contains(&card_idx) {
+            return Err(AuthServiceError::AvatarNotFound(card_idx));
+        }
+        if self.data_layer.is_card_idx_claimed(card_idx).await? {
+            return Err(AuthServiceError::AvatarAlreadyClaimed(card_idx));
+        }
+        if self.data_layer.get_user_by_email(email).await?.is_some() {
+            return Err(AuthServiceError::EmailAlreadyRegistered(email.to_string()));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Returns `TooManyLoginAttempts` if logins with the `email` are backed off or locked out,
+    /// or too many logins have failed from the `ip` address recently
+    /// 
+    async fn check_login_throttle(&self, email: &str, ip: Option<&str>) -> Result<()> {
+        let now = Utc::now().naive_utc();
+
+        let since = now - Duration::seconds(self.settings.login_lockout_s);
+        let failures = self.data_layer.get_account_login_failures(email, since).await?;
+        let mut retry_on = account_retry_on(&failures, &self.settings);
+
+        if let Some(ip) = ip {
+            let since = now - Duration::seconds(self.settings.login_ip_window_s);
+            let failures = self.data_layer.get_ip_login_failures(ip, since).await?;
+            if failures.count >= self.settings.login_ip_max_attempts {
+                // The window slides past the oldest failure first
+                let ip_retry_on = failures.first_attempted_on
+                    .map(|first| first + Duration::seconds(self.settings.login_ip_window_s));
+                retry_on = retry_on.max(ip_retry_on);
+            }
+        }
+
+        match retry_on {
+            Some(retry_on) if retry_on > now => {
+                let retry_after_s = (retry_on - now).num_seconds() + 1;
+                warn!("Throttled login for email {} from IP {:?} for {}s", email, ip, retry_after_s);
+                Err(AuthServiceError::TooManyLoginAttempts(retry_after_s))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+///
+/// Hashes the password with a newly generated random salt
+///
+fn hash_pwd(pwd: &str, settings: &AuthSettings) -> Result<String> {
+    let mut salt = vec![0u8; settings.salt_length];
+    OsRng.fill_bytes(&mut salt);
+    argon2::hash_encoded(pwd.as_bytes(), &salt, &settings.hash_config())
+        .map_err(AuthServiceError::PasswordHashError)
+}
+
+///
+/// Checks if the encoded hash was made with the shared legacy salt, 
+/// or with other parameters than are currently configured
+///
+fn needs_rehash(pwd_hash: &str, settings: &AuthSettings) -> bool {
+    let config = settings.hash_config();
+    let params = format!(
+        "${}$v={}$m={},t={},p={}$", 
+        config.variant, config.version, config.mem_cost, config.time_cost, config.lanes
+    );
+    let Some(salt) = pwd_hash.strip_prefix(&params).and_then(|rest| rest.split('$').next()) else {
+        return true;
+    };
+    salt == general_purpose::STANDARD_NO_PAD.encode(SALT.as_bytes())
+}
+
+///
+/// Returns when the account with the given consecutive login `failures` may attempt to login again.
+/// Past `login_free_attempts`, the delay doubles with each failure until the account is locked out
+///
+fn account_retry_on(failures: &LoginFailuresModel, settings: &AuthSettings) -> Option<NaiveDateTime> {
+    let last_attempted_on = failures.last_attempted_on?;
+    if failures.count >= settings.login_lockout_attempts {
+        return Some(last_attempted_on + Duration::seconds(settings.login_lockout_s));
+    }
+
+    let backoffs = failures.count - settings.login_free_attempts;
+    if backoffs < 0 {
+        return None;
+    }
+    let delay_s = settings.login_backoff_base_s.saturating_mul(1 << backoffs.min(30));
+    Some(last_attempted_on + Duration::seconds(delay_s.min(settings.login_lockout_s)))
+}
+
+///
+/// Checks that the password is long enough, and mixes letters and digits
+/// 
+fn is_strong_pwd(pwd: &str) -> bool {
+    pwd.chars().count() >= MIN_PWD_LENGTH
+        && pwd.chars().any(|c| c.is_alphabetic())
+        && pwd.chars().any(|c| c.is_ascii_digit())
+}
+
+async fn revoke_token(refr_token: RefrTokenModel, data_layer: &Arc<dyn AuthDataLayer>, revoked_by: &str) -> data_layer_error::Result<i64> {
     let mut desc_token = refr_token;
 
     // Traverse down the descendent token line, finding the
@@ -143,7 +441,80 @@ async fn revoke_token(refr_token: RefrTokenModel, data_layer: &Arc<dyn AuthDataL
         desc_token = data_layer.get_refr_token_by_id(next_token_id).await?.unwrap();
     }
     
-    data_layer.revoke_refr_token(desc_token.id, None, "SERVER (DUPL. USAGE)").await?;
+    data_layer.revoke_refr_token(desc_token.id, None, revoked_by).await?;
 
     Ok(desc_token.id)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argon2::Config;
+
+    fn settings() -> AuthSettings {
+        AuthSettings {
+            argon2_mem_cost_kib: 8,
+            argon2_time_cost: 1,
+            argon2_lanes: 1,
+            salt_length: 16,
+            reset_token_lifetime_s: 60,
+            login_free_attempts: 3,
+            login_backoff_base_s: 2,
+            login_lockout_attempts: 10,
+            login_lockout_s: 900,
+            login_ip_max_attempts: 50,
+            login_ip_window_s: 900,
+        }
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        let settings = settings();
+
+        // Hashes made with the shared legacy salt must be rehashed, whatever their parameters
+        let legacy_hash = argon2::hash_encoded(b"password1", SALT.as_bytes(), &Config::default()).unwrap();
+        assert!(needs_rehash(&legacy_hash, &settings));
+        let legacy_hash = argon2::hash_encoded(b"password1", SALT.as_bytes(), &settings.hash_config()).unwrap();
+        assert!(needs_rehash(&legacy_hash, &settings));
+
+        // As must hashes made with other memory, time or lane parameters
+        let pwd_hash = hash_pwd("password1", &settings).unwrap();
+        assert!(needs_rehash(&pwd_hash, &AuthSettings { argon2_mem_cost_kib: 16, ..settings.clone() }));
+        assert!(needs_rehash(&pwd_hash, &AuthSettings { argon2_time_cost: 2, ..settings.clone() }));
+        assert!(needs_rehash(&pwd_hash, &AuthSettings { argon2_lanes: 2, ..settings.clone() }));
+
+        assert!(!needs_rehash(&pwd_hash, &settings));
+    }
+
+    #[test]
+    fn test_hash_pwd() {
+        let settings = settings();
+        let first = hash_pwd("password1", &settings).unwrap();
+        let second = hash_pwd("password1", &settings).unwrap();
+
+        // Every hash gets its own salt, but both verify
+        assert_ne!(first, second);
+        assert!(argon2::verify_encoded(&first, b"password1").unwrap());
+        assert!(argon2::verify_encoded(&second, b"password1").unwrap());
+        assert!(!argon2::verify_encoded(&first, b"password2").unwrap());
+    }
+
+    #[test]
+    fn test_account_retry_on() {
+        let settings = settings();
+        let last = Utc::now().naive_utc();
+        let failures = |count| LoginFailuresModel { count, first_attempted_on: Some(last), last_attempted_on: Some(last) };
+
+        // No delay without failures, or within the free attempts
+        assert_eq!(account_retry_on(&LoginFailuresModel::default(), &settings), None);
+        assert_eq!(account_retry_on(&failures(2), &settings), None);
+
+        // The delay doubles with every failure past the free attempts
+        assert_eq!(account_retry_on(&failures(3), &settings), Some(last + Duration::seconds(2)));
+        assert_eq!(account_retry_on(&failures(5), &settings), Some(last + Duration::seconds(8)));
+
+        // Until the account is locked out
+        assert_eq!(account_retry_on(&failures(10), &settings), Some(last + Duration::seconds(900)));
+        assert_eq!(account_retry_on(&failures(100), &settings), Some(last + Duration::seconds(900)));
+    }
+}
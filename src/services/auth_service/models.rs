@@ -1,17 +1,79 @@
 use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::services::token_service::models::Role;
 
 #[derive(Debug, Default)]
 pub struct UserModel { 
     pub id: i64, 
     pub email: String, 
     pub pwd_hash: String, 
+    pub approved: bool,
+    pub role: Role,
+}
+
+///
+/// A newly registered user. Users registered with a valid invite code
+/// are approved immediately, others must be approved by an admin
+/// 
+#[derive(Debug, Serialize)]
+pub struct RegisteredUserModel {
+    pub user_id: i64,
+    pub approved: bool,
+}
+
+///
+/// The outcome of inserting a registering user, the unique email and
+/// avatar constraints are what decide a race between two registrations
+/// 
+#[derive(Debug)]
+pub enum RegistrationOutcome {
+    Registered(i64),
+    InvalidInviteCode,
+    EmailTaken,
+    AvatarTaken,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PendingUserModel {
+    pub id: i64,
+    pub email: String,
+    pub card_idx: i64,
 }
 
 #[derive(Clone, Default)]
 pub struct RefrTokenModel {
     pub id: i64,
     pub user_id: i64,
-    pub token: String,
+    pub token_hash: Option<String>,
     pub repl_id: Option<i64>,
+    pub created_on: NaiveDateTime,
+    pub session_started_on: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub expires: Option<NaiveDateTime>,
     pub revoked_on: Option<NaiveDateTime>
-}
\ No newline at end of file
+}
+
+///
+/// A signed in session, identified by its current refresh token.
+/// `current` is set for the session the request was made from
+/// 
+#[derive(Debug, Serialize)]
+pub struct SessionModel {
+    pub id: i64,
+    pub started_on: NaiveDateTime,
+    pub last_refreshed_on: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub current: bool,
+}
+
+///
+/// The failed login attempts made within a period.
+/// The timestamps are `None` when there are no failures
+/// 
+#[derive(Debug, Default)]
+pub struct LoginFailuresModel {
+    pub count: i64,
+    pub first_attempted_on: Option<NaiveDateTime>,
+    pub last_attempted_on: Option<NaiveDateTime>,
+}
@@ -0,0 +1,78 @@
+use argon2::{Config, Variant};
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize)]
+pub struct AuthSettings {
+    ///
+    /// The memory argon2 uses to hash a password, in KiB
+    /// 
+    pub argon2_mem_cost_kib: u32,
+    ///
+    /// The number of passes argon2 makes over its memory
+    /// 
+    pub argon2_time_cost: u32,
+    ///
+    /// The number of lanes argon2 hashes with
+    /// 
+    pub argon2_lanes: u32,
+    ///
+    /// The length of the random salt generated for every password hash, in bytes
+    /// 
+    pub salt_length: usize,
+    ///
+    /// How long an admin-issued password reset token can be redeemed for, in seconds
+    /// 
+    pub reset_token_lifetime_s: i64,
+    ///
+    /// The number of consecutive failed logins an account is allowed before
+    /// further attempts are delayed with exponential backoff
+    /// 
+    pub login_free_attempts: i64,
+    ///
+    /// The delay after the first failed login past `login_free_attempts`, in seconds.
+    /// It doubles with every further failure
+    /// 
+    pub login_backoff_base_s: i64,
+    ///
+    /// The number of consecutive failed logins which lock an account
+    /// 
+    pub login_lockout_attempts: i64,
+    ///
+    /// How long an account stays locked after its latest failed login, in seconds.
+    /// Failures older than this are forgotten
+    /// 
+    pub login_lockout_s: i64,
+    ///
+    /// The number of failed logins an IP address is allowed within `login_ip_window_s`
+    /// 
+    pub login_ip_max_attempts: i64,
+    ///
+    /// The sliding window failed logins are counted against an IP address in, in seconds
+    /// 
+    pub login_ip_window_s: i64,
+}
+
+impl AuthSettings {
+    ///
+    /// Returns the argon2 config passwords are hashed with
+    /// 
+    pub fn hash_config(&self) -> Config<'static> {
+        Config {
+            variant: Variant::Argon2id,
+            mem_cost: self.argon2_mem_cost_kib,
+            time_cost: self.argon2_time_cost,
+            lanes: self.argon2_lanes,
+            ..Config::default()
+        }
+    }
+
+    ///
+    /// Ensures passwords can be hashed with the configured argon2 parameters
+    /// and salt length, by hashing an empty password
+    /// 
+    pub fn validate(&self) -> Result<(), argon2::Error> {
+        let salt = vec![0u8; self.salt_length];
+        argon2::hash_encoded(b"", &salt, &self.hash_config())?;
+        Ok(())
+    }
+}
@@ -13,15 +13,15 @@ pub const IDLE_IDX: i64 = 2;
 
 #[async_trait]
 pub trait BattleDataLayer : Send + Sync {
-    async fn get_monst_state(&self, user_id: i64) -> Result<MonsterState>;
+    async fn get_monst_state(&self, game_id: i64, user_id: i64) -> Result<MonsterState>;
     async fn set_monst_next_action(&self, monst_id: i64, next_action: &NextAction) -> Result<()>;
-    async fn get_pl_power(&self, user_id: i64) -> Result<i64>;
-    async fn dmg_monst(&self, user_id: i64, pl_power: i64, dmg: i64) -> Result<(i64, bool)>;
-    async fn expend_pl_pow(&self, pl_id: i64) -> Result<()>;
+    async fn get_pl_power(&self, game_id: i64, user_id: i64) -> Result<i64>;
+    async fn dmg_monst(&self, game_id: i64, user_id: i64, pl_power: i64, dmg: i64) -> Result<(i64, bool)>;
+    async fn expend_pl_pow(&self, game_id: i64, pl_id: i64) -> Result<()>;
     async fn expend_monst_pow(&self, monst_id: i64) -> Result<()>;
-    async fn dmg_pl(&self, user_id: i64, dmg: i64) -> Result<()>;
-    async fn get_pl_and_monst_stats(&self, user_id: i64) -> Result<(Stats, Stats)>;
-    async fn increment_pl_pow(&self, user_id: i64, max_pow: i64) -> Result<()>;
+    async fn dmg_pl(&self, game_id: i64, user_id: i64, dmg: i64) -> Result<()>;
+    async fn get_pl_and_monst_stats(&self, game_id: i64, user_id: i64) -> Result<(Stats, Stats)>;
+    async fn increment_pl_pow(&self, game_id: i64, user_id: i64, max_pow: i64) -> Result<()>;
     async fn increment_monst_pow(&self, monst_id: i64, max_pow: i64) -> Result<()>;
 }
 
@@ -32,13 +32,13 @@ pub struct DataLayer {
 
 #[async_trait]
 impl BattleDataLayer for DataLayer {
-    async fn get_monst_state(&self, user_id: i64) -> Result<MonsterState> {
-        // Gather the monster state associated with the user's active quest
+    async fn get_monst_state(&self, game_id: i64, user_id: i64) -> Result<MonsterState> {
+        // Gather the monster state associated with the user's active quest in the game
         let monster = sqlx::query!("
             SELECT ms.id, ms.monster_idx, ms.stats_id, ms.next_action, ms.action_flv_text, s.health, s.power, s.armor, s.missing_next_turn
             FROM quests q JOIN monster_states ms ON q.id = ms.quest_id JOIN stats s ON ms.stats_id = s.id
-            WHERE q.user_id = ? AND q.completed = FALSE
-            ", user_id
+            WHERE q.game_id = ? AND q.user_id = ? AND q.completed = FALSE
+            ", game_id, user_id
         ).fetch_one(&self.db).await?;
 
         // Generate the NextAction from the monster state, if it's making a next action
@@ -57,11 +57,12 @@ impl BattleDataLayer for DataLayer {
         Ok(monster)
     }
     
-    async fn get_pl_power(&self, user_id: i64) -> Result<i64> {
+    async fn get_pl_power(&self, game_id: i64, user_id: i64) -> Result<i64> {
         let power = sqlx::query!("
             SELECT s.power FROM user_states us JOIN stats s ON us.stats_id = s.id
-            WHERE us.user_id = ?
-            ", user_id
+            JOIN quests q ON q.game_id = us.game_id AND q.user_id = us.user_id
+            WHERE us.game_id = ? AND us.user_id = ? AND q.completed = FALSE
+            ", game_id, user_id
         ).fetch_one(&self.db).await?.power;
 
         Ok(power)
@@ -75,9 +76,8 @@ impl BattleDataLayer for DataLayer {
         Ok(())
     }
 
-    async fn expend_pl_pow(&self, pl_id: i64) -> Result<()> {
-        let stats_id = sqlx::query!("SELECT stats_id FROM user_states WHERE user_id = ?", pl_id)
-            .fetch_one(&self.db).await?.stats_id;
+    async fn expend_pl_pow(&self, game_id: i64, pl_id: i64) -> Result<()> {
+        let stats_id = self.get_pl_stats_id(game_id, pl_id).await?;
         sqlx::query!("UPDATE stats SET power = 0 WHERE id = ?", stats_id)
             .execute(&self.db).await?;
 
@@ -93,21 +93,20 @@ impl BattleDataLayer for DataLayer {
         Ok(())
     }
 
-    async fn dmg_pl(&self, user_id: i64, dmg: i64) -> Result<()> {
-        let stats_id = sqlx::query!("SELECT stats_id FROM user_states WHERE user_id = ?", user_id)
-            .fetch_one(&self.db).await?.stats_id;
+    async fn dmg_pl(&self, game_id: i64, user_id: i64, dmg: i64) -> Result<()> {
+        let stats_id = self.get_pl_stats_id(game_id, user_id).await?;
         sqlx::query!("UPDATE stats SET health = health - ? WHERE id = ?", dmg, stats_id)
             .execute(&self.db).await?;
 
         Ok(())
     }
 
-    async fn dmg_monst(&self, user_id: i64, pl_power: i64, dmg: i64) -> Result<(i64, bool)> {
-        // Get the monster health from the quest the user is currently on
+    async fn dmg_monst(&self, game_id: i64, user_id: i64, pl_power: i64, dmg: i64) -> Result<(i64, bool)> {
+        // Get the monster health from the quest the user is currently on in the game
         let quest_monster = sqlx::query!("
             SELECT ms.stats_id, s.health, ms.next_action FROM quests q JOIN monster_states ms ON q.id = ms.quest_id JOIN stats s ON ms.stats_id = s.id
-            WHERE q.user_id = ? AND q.completed = FALSE
-            ", user_id
+            WHERE q.game_id = ? AND q.user_id = ? AND q.completed = FALSE
+            ", game_id, user_id
         ).fetch_one(&self.db).await?;
 
         // Get the monster's stats, and divide damage by 2 if it is defending
@@ -122,9 +121,7 @@ impl BattleDataLayer for DataLayer {
         sqlx::query!("UPDATE stats SET health = health - ? WHERE id = ?", dmg, quest_monster.stats_id)
             .execute(&self.db).await?;
 
-        let stats_id = sqlx::query!(
-            "SELECT stats_id FROM user_states WHERE user_id = ?", user_id
-        ).fetch_one(&self.db).await?.stats_id;
+        let stats_id = self.get_pl_stats_id(game_id, user_id).await?;
 
         sqlx::query!(
             "UPDATE stats SET power = power - ? WHERE id = ?", pl_power, stats_id
@@ -133,20 +130,21 @@ impl BattleDataLayer for DataLayer {
         Ok((dmg, false))
     }
 
-    async fn get_pl_and_monst_stats(&self, user_id: i64) -> Result<(Stats, Stats)> {
+    async fn get_pl_and_monst_stats(&self, game_id: i64, user_id: i64) -> Result<(Stats, Stats)> {
         // Get the monster stats and user stats
         let monst_stats = sqlx::query!("
             SELECT s.health, s.power, s.armor, s.missing_next_turn
             FROM quests q JOIN monster_states ms ON q.id = ms.quest_id JOIN stats s ON ms.stats_id = s.id
-            WHERE q.user_id = ? AND q.completed = FALSE
-            ", user_id
+            WHERE q.game_id = ? AND q.user_id = ? AND q.completed = FALSE
+            ", game_id, user_id
         ).fetch_one(&self.db).await?;
   
         let pl_stats = sqlx::query!("
             SELECT s.health, s.power, s.armor, s.missing_next_turn
             FROM user_states us JOIN stats s ON us.stats_id = s.id
-            WHERE us.user_id = ?
-            ", user_id
+            JOIN quests q ON q.game_id = us.game_id AND q.user_id = us.user_id
+            WHERE us.game_id = ? AND us.user_id = ? AND q.completed = FALSE
+            ", game_id, user_id
         ).fetch_one(&self.db).await?;
         
         Ok((
@@ -155,10 +153,12 @@ impl BattleDataLayer for DataLayer {
         ))
     }
 
-    async fn increment_pl_pow(&self, user_id: i64, max_pow: i64) -> Result<()> {
+    async fn increment_pl_pow(&self, game_id: i64, user_id: i64, max_pow: i64) -> Result<()> {
         let stats = sqlx::query!("
-            SELECT s.id, s.power FROM stats s JOIN user_states us ON us.stats_id = s.id WHERE us.user_id = ?
-            ", user_id
+            SELECT s.id, s.power FROM stats s JOIN user_states us ON us.stats_id = s.id 
+            JOIN quests q ON q.game_id = us.game_id AND q.user_id = us.user_id
+            WHERE us.game_id = ? AND us.user_id = ? AND q.completed = FALSE
+            ", game_id, user_id
         ).fetch_one(&self.db).await?;
 
         if stats.power < max_pow {
@@ -185,4 +185,20 @@ impl BattleDataLayer for DataLayer {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl DataLayer {
+    ///
+    /// Retrieves the id of the player's stats in the game with the given `game_id`, if they have an active quest in it
+    /// 
+    async fn get_pl_stats_id(&self, game_id: i64, user_id: i64) -> Result<i64> {
+        Ok(
+            sqlx::query!("
+                SELECT us.stats_id FROM user_states us 
+                JOIN quests q ON q.game_id = us.game_id AND q.user_id = us.user_id
+                WHERE us.game_id = ? AND us.user_id = ? AND q.completed = FALSE
+                ", game_id, user_id
+            ).fetch_one(&self.db).await?.stats_id
+        )
+    }
+}
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use axum::async_trait;
 use derive_more::Constructor;
+use log::error;
 use rand::{thread_rng, RngCore};
 
 use crate::ai::AI;
@@ -11,6 +12,7 @@ use self::data_layer::{BattleDataLayer, ATTACK_IDX};
 use self::error::{Result, BattleServiceError};
 use self::models::{RoundResult, NextAction};
 
+use super::achievement_service::{models::GameEvent, AchievementService};
 use super::game_service::models::Stats;
 use super::quest_service::QuestService;
 
@@ -27,38 +29,39 @@ pub trait BattleService : Send + Sync {
     /// Initializes the battle, or sends the current battle to the player
     /// on initial connection.
     /// 
-    async fn setup(&self, user_id: i64) -> Result<RoundResult>;
-    async fn attack(&self, user_id: i64, power: i64) -> Result<RoundResult>;
-    async fn defend(&self, user_id: i64) -> Result<RoundResult>;
-    async fn use_item(&self, user_id: i64, item_idx: i64) -> Result<RoundResult>;
+    async fn setup(&self, user_id: i64, game_id: i64) -> Result<RoundResult>;
+    async fn attack(&self, user_id: i64, game_id: i64, power: i64) -> Result<RoundResult>;
+    async fn defend(&self, user_id: i64, game_id: i64) -> Result<RoundResult>;
+    async fn use_item(&self, user_id: i64, game_id: i64, item_idx: i64) -> Result<RoundResult>;
 }
 
 #[derive(Constructor)]
 pub struct CoreBattleService {
     data_layer: Arc<dyn BattleDataLayer>,
     quest_service: Arc<dyn QuestService>,
+    achievement_service: Arc<dyn AchievementService>,
     res: Arc<Resources>,
 }
 
 #[async_trait]
 impl BattleService for CoreBattleService {
-    async fn setup(&self, user_id: i64) -> Result<RoundResult> {
-        let (mut pl_stats, mut monst_stats) = self.data_layer.get_pl_and_monst_stats(user_id).await.map_err(|e| e.into())?;
-        let monster_state = self.data_layer.get_monst_state(user_id).await.map_err(|e| e.into())?;
+    async fn setup(&self, user_id: i64, game_id: i64) -> Result<RoundResult> {
+        let (mut pl_stats, mut monst_stats) = self.data_layer.get_pl_and_monst_stats(game_id, user_id).await.map_err(|e| e.into())?;
+        let monster_state = self.data_layer.get_monst_state(game_id, user_id).await.map_err(|e| e.into())?;
         let next_action;
 
         if monster_state.next_action.is_none() {
-            let monst_state = self.data_layer.get_monst_state(user_id).await.map_err(|e| e.into())?;
+            let monst_state = self.data_layer.get_monst_state(game_id, user_id).await.map_err(|e| e.into())?;
             let monst_res = &self.res.monsters[monst_state.res_idx];
 
             next_action = self.get_monster_next_action(monst_res, &pl_stats, &monst_stats);
             self.data_layer.set_monst_next_action(monst_state.db_id, &next_action).await.map_err(|e| e.into())?;
 
-            self.data_layer.expend_pl_pow(user_id).await.map_err(|e| e.into())?;
-            self.data_layer.increment_pl_pow(user_id, MAX_POWER).await.map_err(|e| e.into())?;
+            self.data_layer.expend_pl_pow(game_id, user_id).await.map_err(|e| e.into())?;
+            self.data_layer.increment_pl_pow(game_id, user_id, MAX_POWER).await.map_err(|e| e.into())?;
 
             // Re-retrieve the player and monster stats with them set up
-            (pl_stats, monst_stats) = self.data_layer.get_pl_and_monst_stats(user_id).await.map_err(|e| e.into())?;
+            (pl_stats, monst_stats) = self.data_layer.get_pl_and_monst_stats(game_id, user_id).await.map_err(|e| e.into())?;
         } else {
             next_action = monster_state.next_action.unwrap();
         }
@@ -66,8 +69,8 @@ impl BattleService for CoreBattleService {
         Ok(RoundResult::Next { pl_stats, monst_stats, next_action, pl_dmg_dealt: 0, monst_dmg_dealt: 0, monst_pow_used: 0 })
     }
 
-    async fn attack(&self, user_id: i64, power: i64) -> Result<RoundResult> { 
-        let pl_power = self.data_layer.get_pl_power(user_id).await.map_err(|e| e.into())?;
+    async fn attack(&self, user_id: i64, game_id: i64, power: i64) -> Result<RoundResult> { 
+        let pl_power = self.data_layer.get_pl_power(game_id, user_id).await.map_err(|e| e.into())?;
         // Check that the player has enough power
         if pl_power < power {
             return Err(BattleServiceError::NotEnoughPower);
@@ -80,22 +83,25 @@ impl BattleService for CoreBattleService {
         let dmg = (thread_rng().next_u32() as i64) % (dmg_rng.1 - dmg_rng.0);
         let dmg = dmg_rng.0 + dmg;
 
-        let (dmg, defeated) = self.data_layer.dmg_monst(user_id, power, dmg).await.map_err(|e| e.into())?;
+        let (dmg, defeated) = self.data_layer.dmg_monst(game_id, user_id, power, dmg).await.map_err(|e| e.into())?;
 
         // Damage the monster, and test if it's been defeated
         return if defeated {
             // If it was defeated, complete the quest and return the victory signal, with rewards
-            let reward = self.quest_service.complete_quest(user_id).await.map_err(|e| BattleServiceError::QuestServiceError(e))?;
+            let reward = self.quest_service.complete_quest(user_id, game_id).await.map_err(|e| BattleServiceError::QuestServiceError(e))?;
+            if let Err(e) = self.achievement_service.handle_event(user_id, GameEvent::MonsterDefeated).await {
+                error!("Failed to unlock achievements for user {} defeating a monster: {:?}", user_id, e);
+            }
             Ok(RoundResult::Victory { reward, pl_dmg_dealt: dmg })
         } else {
             // Otherwise, perform the monster's action, and return the results
-            self.perform_monster_action(user_id, false, dmg).await.map_err(|e| e.into())
+            self.perform_monster_action(user_id, game_id, false, dmg).await.map_err(|e| e.into())
         }
     }
-    async fn defend(&self, user_id: i64) -> Result<RoundResult> {
-        self.perform_monster_action(user_id, true, 0).await.map_err(|e| e.into())
+    async fn defend(&self, user_id: i64, game_id: i64) -> Result<RoundResult> {
+        self.perform_monster_action(user_id, game_id, true, 0).await.map_err(|e| e.into())
     }
-    async fn use_item(&self, _user_id: i64, _item_idx: i64) -> Result<RoundResult> { 
+    async fn use_item(&self, _user_id: i64, _game_id: i64, _item_idx: i64) -> Result<RoundResult> { 
         todo!();
     }
 }
@@ -129,10 +135,10 @@ impl CoreBattleService {
     /// Performs the monster's action, damaging the player if attacking,
     /// and generating its next action
     /// 
-    async fn perform_monster_action(&self, user_id: i64, pl_defd: bool, pl_dmg_dealt: i64) -> Result<RoundResult> {
+    async fn perform_monster_action(&self, user_id: i64, game_id: i64, pl_defd: bool, pl_dmg_dealt: i64) -> Result<RoundResult> {
         // Get the current state of the Monster, and current player and Monster Stats
-        let (pl_stats, monst_stats) = self.data_layer.get_pl_and_monst_stats(user_id).await.map_err(|e| e.into())?;
-        let monst_state = self.data_layer.get_monst_state(user_id).await.map_err(|e| e.into())?;
+        let (pl_stats, monst_stats) = self.data_layer.get_pl_and_monst_stats(game_id, user_id).await.map_err(|e| e.into())?;
+        let monst_state = self.data_layer.get_monst_state(game_id, user_id).await.map_err(|e| e.into())?;
         let monst_res = &self.res.monsters[monst_state.res_idx];
         let mut monst_dmg_dealt = 0i64;
         let mut monst_pow_used = 0i64;
@@ -142,18 +148,21 @@ impl CoreBattleService {
             monst_dmg_dealt = self.get_monster_dmg(&monst_stats, &self.res.monsters[monst_state.res_idx], pl_defd);
             monst_pow_used = monst_stats.power;
             if monst_dmg_dealt >= pl_stats.health {
-                let consq = self.quest_service.fail_quest(user_id).await.map_err(|e| BattleServiceError::QuestServiceError(e))?;
+                let consq = self.quest_service.fail_quest(user_id, game_id).await.map_err(|e| BattleServiceError::QuestServiceError(e))?;
+                if let Err(e) = self.achievement_service.handle_event(user_id, GameEvent::BattleLost).await {
+                    error!("Failed to unlock achievements for user {} losing a battle: {:?}", user_id, e);
+                }
                 return Ok(RoundResult::Defeat { monst_dmg: monst_dmg_dealt, consq, pl_dmg_dealt, monst_pow_used: monst_stats.power })
             }
-            self.data_layer.dmg_pl(user_id, monst_dmg_dealt).await.map_err(|e| e.into())?;
+            self.data_layer.dmg_pl(game_id, user_id, monst_dmg_dealt).await.map_err(|e| e.into())?;
             self.data_layer.expend_monst_pow(monst_state.db_id).await.map_err(|e| e.into())?;
         }
         // Increment the player and monster's power by 1
-        self.data_layer.increment_pl_pow(user_id, MAX_POWER).await.map_err(|e| e.into())?;
+        self.data_layer.increment_pl_pow(game_id, user_id, MAX_POWER).await.map_err(|e| e.into())?;
         self.data_layer.increment_monst_pow(monst_state.db_id, monst_res.pow_dmg.len() as i64).await.map_err(|e| e.into())?;
 
         // Determine the monster's next action, and new Stats
-        let (pl_stats, monst_stats) = self.data_layer.get_pl_and_monst_stats(user_id).await.map_err(|e| e.into())?;
+        let (pl_stats, monst_stats) = self.data_layer.get_pl_and_monst_stats(game_id, user_id).await.map_err(|e| e.into())?;
         let next_action = self.get_monster_next_action(monst_res, &pl_stats, &monst_stats);
         self.data_layer.set_monst_next_action(monst_state.db_id, &next_action).await.map_err(|e| e.into())?;
                 
@@ -1,57 +1,155 @@
 use axum::async_trait;
 use chrono::Utc;
 use derive_more::Constructor;
-use sqlx::SqlitePool;
+use sqlx::{Executor, Sqlite, SqlitePool};
 
-use crate::{data_layer_error::Result, resources::game_resources::BaseStats};
+use crate::{data_layer_error::Result, resources::game_resources::BaseStats, services::quest_service::models::QuestKind};
 
-use super::models::{CardModel, GameStateModel, Stats, UserCardModel};
-
-const PERSON_CAT_IDX: i32 = 0;
+use super::{entities::{GamePlayerEntity, NewGameEntity, NotebookCardEntity, TradeAcceptance, TradeEntity}, models::{ArchivedGameModel, CardModel, CardSource, CardState, GameModel, GameStateModel, LeaderboardEntryModel, NotebookUpdateModel, StandingModel, Stats, TargetGuessModel, TradeModel, TradeStatus}};
 
 #[async_trait]
 pub trait GameDataLayer : Send + Sync {
     ///
-    /// Checks if there is a game currently running
+    /// Checks if the game with the given `game_id` exists, has not been archived,
+    /// and has not passed its deadline
     /// 
-    async fn is_game_active(&self) -> Result<bool>;
-    async fn pl_guessed_today(&self, user_id: i64) -> Result<bool>;
-    async fn update_guessed_today(&self, user_id: i64) -> Result<()>;
+    async fn is_game_active(&self, game_id: i64) -> Result<bool>;
+    ///
+    /// Checks if the game with the given `game_id` exists and has not been archived,
+    /// regardless of its deadline
+    ///
+    async fn is_game_unarchived(&self, game_id: i64) -> Result<bool>;
+    ///
+    /// Checks if the user with the given `user_id` has joined the game with the given `game_id`
+    ///
+    async fn is_pl_in_game(&self, game_id: i64, user_id: i64) -> Result<bool>;
+    ///
+    /// Marks the player as having guessed today in the game with the given `game_id`.
+    /// Returns `false` if they had already guessed today
+    ///
+    async fn update_guessed_today(&self, game_id: i64, user_id: i64) -> Result<bool>;
+    ///
+    /// Retrieves the person card index of the user with the given `user_id`, if they exist
+    ///
+    async fn get_user_card_idx(&self, user_id: i64) -> Result<Option<i64>>;
+    ///
+    /// Creates the new `game` in a single transaction, creating its new players and adding
+    /// every player with the given `base_stats`. Returns the ids of the game and murdered user
+    ///
+    async fn create_game<'a>(&self, game: &'a NewGameEntity, base_stats: &'a BaseStats) -> Result<(i64, i64)>;
+    ///
+    /// Adds the user to the game with the given `game_id`, starting with the given `base_stats`
+    ///
+    async fn add_player<'a>(&self, game_id: i64, user_id: i64, base_stats: &'a BaseStats) -> Result<()>;
+    ///
+    /// Retrieves every game, noting which the user with the given `user_id` has joined
+    ///
+    async fn get_games(&self, user_id: i64) -> Result<Vec<GameModel>>;
+    ///
+    /// Marks the game with the given `game_id` as archived, ending it and recording 
+    /// the final standings of its players
+    ///
+    async fn archive_game(&self, game_id: i64) -> Result<()>;
+    ///
+    /// Retrieves the final standings of the game with the given `game_id`, in order of place.
+    /// Empty if the game has not ended
+    ///
+    async fn get_standings(&self, game_id: i64) -> Result<Vec<StandingModel>>;
+    ///
+    /// Removes all player state of the game with the given `game_id` - their progress, quests and 
+    /// cards - leaving only the game's players, target cards and winners. Players are restored to 
+    /// the first level, at the given `base_stats`
+    ///
+    async fn reset_game<'a>(&self, game_id: i64, base_stats: &'a BaseStats) -> Result<()>;
+    ///
+    /// Checks if the game with the given `game_id` has as many winners as its winner limit
+    ///
+    async fn winner_limit_reached(&self, game_id: i64) -> Result<bool>;
     ///
-    /// Sets up the game with the given target cards (excluding the murdered user card,
-    /// as that is generated in this process), and base user stats. 
-    /// Returns the randomly chosen murdered user.
+    /// Retrieves the game with the given `game_id`, if it exists and has been archived
     ///
-    async fn setup_game<'a>(&self, target_cards: &'a [CardModel], base_stats: &'a BaseStats) -> Result<()>;
+    async fn get_archived_game(&self, game_id: i64) -> Result<Option<ArchivedGameModel>>;
     /// 
     /// Returns all current game state data, as it pertains to the particular user
     /// (ie. if the user has won, their collection of evidence cards, etc.)
     /// 
-    async fn game_state(&self, user_id: i64) -> Result<Option<GameStateModel>>;
+    async fn game_state(&self, game_id: i64, user_id: i64) -> Result<Option<GameStateModel>>;
     ///
-    /// Gets all target cards in the current game.
+    /// Gets all target cards in the game with the given `game_id`.
     /// The cards users have to guess to win.
     /// 
-    async fn get_target_cards(&self) -> Result<Vec<CardModel>>;
+    async fn get_target_cards(&self, game_id: i64) -> Result<Vec<CardModel>>;
     ///
-    /// Assigns the user to the winner collection
+    /// Checks if incorrect guesses in the game with the given `game_id` report 
+    /// how many categories were right
     /// 
-    async fn add_new_winner(&self, user_id: i64) -> Result<()>;
+    async fn has_partial_feedback(&self, game_id: i64) -> Result<bool>;
     ///
-    /// Updates the status of a user's card, if required.
-    /// Unconfirmed cards which are no longer guessed are removed, while
-    /// Unconfirmed cards that are guessed are added to the user's data (if needed)
+    /// Records the user's guess of the target cards, with one card per category
     /// 
-    async fn update_user_card(&self, user_id: i64, cat_idx: i64, card_idx: i64, guessed: bool) -> Result<()>;
+    async fn add_target_guess<'a>(&self, game_id: i64, user_id: i64, guess: &'a [CardModel], correct: bool, correct_cats: Option<i64>) -> Result<()>;
     ///
-    /// Retrieves the indices of all evidence cards that the user has confirmed
+    /// Retrieves the user's guesses of the target cards in the game, oldest first
     /// 
-    async fn get_confirmed_user_cards(&self, user_id: i64) -> Result<Vec<CardModel>>;
+    async fn get_target_guesses(&self, game_id: i64, user_id: i64) -> Result<Vec<TargetGuessModel>>;
     ///
-    /// Adds card to user's confirmed set, marked as `confirmed`
+    /// Assigns the user to the winner collection of the game
     /// 
-    async fn confirm_user_card(&self, user_id: i64, cat_idx: i64, card_idx: i64) -> Result<()>;
-    async fn get_completed_riddle_count(&self, user_id: i64) -> Result<i64>;
+    async fn add_new_winner(&self, game_id: i64, user_id: i64) -> Result<()>;
+    ///
+    /// Retrieves every card in the user's notebook for the game, with the quest that 
+    /// revealed each confirmed card
+    ///
+    async fn get_notebook(&self, game_id: i64, user_id: i64) -> Result<Vec<NotebookCardEntity>>;
+    ///
+    /// Applies the given `updates` to the user's notebook for the game, all at once.
+    /// Unconfirmed cards left without a state or note are removed, while 
+    /// confirmed cards only have their note updated
+    ///
+    async fn update_notebook<'a>(&self, game_id: i64, user_id: i64, updates: &'a [NotebookUpdateModel]) -> Result<()>;
+    ///
+    /// Marks the card as confirmed in the user's notebook, revealed by the given `source`, if any
+    ///
+    async fn confirm_user_card(&self, game_id: i64, user_id: i64, cat_idx: i64, card_idx: i64, source: Option<CardSource>) -> Result<()>;
+    ///
+    /// Checks if the user has the given `card` confirmed in the game
+    ///
+    async fn is_card_confirmed<'a>(&self, game_id: i64, user_id: i64, card: &'a CardModel) -> Result<bool>;
+    ///
+    /// Retrieves the number of trades the user has made today, in the game with the given `game_id`
+    ///
+    async fn get_pl_trades_today(&self, game_id: i64, user_id: i64) -> Result<i64>;
+    ///
+    /// Creates a pending trade, offering the `card` of the user with `from_user_id` 
+    /// to the user with `to_user_id`. Returns the id of the new trade
+    ///
+    async fn create_trade<'a>(&self, game_id: i64, from_user_id: i64, to_user_id: i64, card: &'a CardModel) -> Result<i64>;
+    ///
+    /// Retrieves the trade with the given `trade_id` in the game, if it exists
+    ///
+    async fn get_trade(&self, game_id: i64, trade_id: i64) -> Result<Option<TradeEntity>>;
+    ///
+    /// Retrieves every trade in the game which the user has offered or received, newest first
+    ///
+    async fn get_trades(&self, game_id: i64, user_id: i64) -> Result<Vec<TradeEntity>>;
+    ///
+    /// Resolves the pending trade with the given `trade_id` with the given `status`, and the card
+    /// returned for it, if accepted. Returns `false` if the trade was no longer pending
+    ///
+    async fn resolve_trade<'a>(&self, trade_id: i64, status: TradeStatus, returned_card: Option<&'a CardModel>) -> Result<bool>;
+    ///
+    /// Accepts the pending `trade` in the game in a single transaction, for the `returned_card`. Counts the
+    /// trade towards both players' `daily_trade_limit`, and confirms the card each player received
+    ///
+    async fn accept_trade<'a>(&self, game_id: i64, trade: &'a TradeModel, returned_card: &'a CardModel, daily_trade_limit: i64) -> Result<TradeAcceptance>;
+    ///
+    /// Retrieves the number of distinct riddles the user has solved in the game with the given `game_id`
+    ///
+    async fn get_solved_riddle_count(&self, game_id: i64, user_id: i64) -> Result<i64>;
+    ///
+    /// Retrieves the progress of every player in the game, with the place of those who have won
+    ///
+    async fn get_leaderboard(&self, game_id: i64) -> Result<Vec<LeaderboardEntryModel>>;
 }
 
 #[derive(Constructor)]
@@ -61,116 +159,272 @@ pub struct DbGameDataLayer {
 
 #[async_trait]
 impl GameDataLayer for DbGameDataLayer {
-    async fn is_game_active(&self) -> Result<bool> {
-        // Get the game state from the database (there should only be one)
+    async fn is_game_active(&self, game_id: i64) -> Result<bool> {
+        let now = Utc::now().naive_utc();
+        Ok(
+            sqlx::query!(
+                "SELECT id FROM game_states WHERE id = ? AND archived_on IS NULL AND (deadline IS NULL OR deadline > ?)",
+                game_id, now
+            ).fetch_optional(&self.db).await?.is_some()
+        )
+    }
+
+    async fn is_game_unarchived(&self, game_id: i64) -> Result<bool> {
         Ok(
-            sqlx::query!("SELECT * FROM game_states")
+            sqlx::query!("SELECT id FROM game_states WHERE id = ? AND archived_on IS NULL", game_id)
             .fetch_optional(&self.db).await?.is_some()
         )
     }
 
-    async fn pl_guessed_today(&self, user_id: i64) -> Result<bool> {
+    async fn is_pl_in_game(&self, game_id: i64, user_id: i64) -> Result<bool> {
         Ok(
-            sqlx::query!("SELECT guessed_today FROM users WHERE id = ?", user_id)
-                .fetch_one(&self.db).await?.guessed_today
+            sqlx::query!("SELECT user_id FROM user_states WHERE game_id = ? AND user_id = ?", game_id, user_id)
+            .fetch_optional(&self.db).await?.is_some()
         )
     }
 
-    async fn update_guessed_today(&self, user_id: i64) -> Result<()> {
-        sqlx::query!("UPDATE users SET guessed_today = TRUE WHERE id = ?", user_id)
-            .execute(&self.db).await?;
-        Ok(())
+    async fn update_guessed_today(&self, game_id: i64, user_id: i64) -> Result<bool> {
+        Ok(
+            sqlx::query!(
+                "UPDATE user_states SET guessed_today = TRUE WHERE game_id = ? AND user_id = ? AND guessed_today = FALSE", 
+                game_id, user_id
+            ).execute(&self.db).await?.rows_affected() > 0
+        )
     }
 
-    async fn setup_game<'a>(&self, target_cards: &'a [CardModel], base_stats: &'a BaseStats) -> Result<()> {
-        // Get all user ids and card_idxs
-        let users = sqlx::query!("SELECT id, card_idx FROM users")
-            .fetch_all(&self.db).await?;
+    async fn get_user_card_idx(&self, user_id: i64) -> Result<Option<i64>> {
+        Ok(
+            sqlx::query!("SELECT card_idx FROM users WHERE id = ?", user_id)
+                .fetch_optional(&self.db).await?
+                .map(|row| row.card_idx)
+        )
+    }
 
-        // Get all other user ids
-        let user_ids: Vec<i64> = users.iter().map(|u| u.id).collect();
+    async fn create_game<'a>(&self, game: &'a NewGameEntity, base_stats: &'a BaseStats) -> Result<(i64, i64)> {
+        let mut tx = self.db.begin().await?;
+
+        // Create the new players, so nothing is left behind if any part of the setup fails
+        let mut user_ids = Vec::new();
+        for player in &game.players {
+            let user_id = match player {
+                GamePlayerEntity::Existing(user_id) => *user_id,
+                GamePlayerEntity::New { email, pwd_hash, card_idx } => sqlx::query!("
+                    INSERT INTO users (email, pwd_hash, card_idx) VALUES (?, ?, ?)
+                    ", email, pwd_hash, card_idx
+                ).execute(&mut *tx).await?.last_insert_rowid(),
+            };
+            user_ids.push(user_id);
+        }
+        let murdered_user_id = user_ids[game.murdered_player];
+
+        // Add the initialized game state
+        let game_id = sqlx::query!(
+            "INSERT INTO game_states (name, winner_limit, deadline, partial_feedback, murdered_user_id) VALUES (?, ?, ?, ?, ?)",
+            game.name, game.winner_limit, game.deadline, game.partial_feedback, murdered_user_id
+        ).execute(&mut *tx).await?.last_insert_rowid();
 
-        // Insert base player stats for each user
-        for id in user_ids {
-            // Create the user's stats and add to the database
+        // Insert each generated target card into the game_target_cards table
+        for target_card in &game.target_cards {
+            sqlx::query!("
+                INSERT INTO game_target_cards (game_id, cat_idx, card_idx) VALUES (?, ?, ?)
+                ", game_id, target_card.cat_idx, target_card.card_idx
+            ).execute(&mut *tx).await?;
+        }
+
+        // Add every player with their own stats
+        for user_id in user_ids {
             let stats_id = sqlx::query!("
                 INSERT INTO STATS (health, armor) VALUES (?, ?)
                 ", base_stats.health, base_stats.armor
-            ).execute(&self.db).await?.last_insert_rowid();
+            ).execute(&mut *tx).await?.last_insert_rowid();
 
-            // Associate the user to their stats
             sqlx::query!("
-                INSERT INTO user_states (user_id, stats_id) VALUES (?, ?)
-                ", id, stats_id,
-            ).execute(&self.db).await?;
+                INSERT INTO user_states (game_id, user_id, stats_id) VALUES (?, ?, ?)
+                ", game_id, user_id, stats_id,
+            ).execute(&mut *tx).await?;
         }
 
-        // Insert each generated target card into the game_target_cards table
-        for target_card in target_cards {
-            sqlx::query!("
-                INSERT INTO game_target_cards (cat_idx, card_idx) VALUES (?, ?)
-                ", target_card.cat_idx, target_card.card_idx
-            ).execute(&self.db).await?;
-        }
+        tx.commit().await?;
+        Ok((game_id, murdered_user_id))
+    }
 
-        // Add the initialized game state
-        sqlx::query!("INSERT INTO game_states (murdered_user_id) VALUES (?)", 4)
-            .execute(&self.db).await?;
+    async fn add_player<'a>(&self, game_id: i64, user_id: i64, base_stats: &'a BaseStats) -> Result<()> {
+        // Create the user's stats and add to the database
+        let stats_id = sqlx::query!("
+            INSERT INTO STATS (health, armor) VALUES (?, ?)
+            ", base_stats.health, base_stats.armor
+        ).execute(&self.db).await?.last_insert_rowid();
+
+        // Associate the user to their stats in the game
+        sqlx::query!("
+            INSERT INTO user_states (game_id, user_id, stats_id) VALUES (?, ?, ?)
+            ", game_id, user_id, stats_id,
+        ).execute(&self.db).await?;
+
+        Ok(())
+    }
+
+    async fn get_games(&self, user_id: i64) -> Result<Vec<GameModel>> {
+        Ok(
+            sqlx::query_as!(GameModel, r#"
+                SELECT gs.id, gs.name, gs.created_on, gs.deadline, gs.archived_on,
+                    EXISTS (SELECT * FROM user_states us WHERE us.game_id = gs.id AND us.user_id = ?) AS "joined: bool"
+                FROM game_states gs ORDER BY gs.id ASC
+                "#, user_id
+            ).fetch_all(&self.db).await?
+        )
+    }
+
+    async fn archive_game(&self, game_id: i64) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+
+        let now = Utc::now().naive_utc();
+        sqlx::query!("UPDATE game_states SET archived_on = ? WHERE id = ?", now, game_id)
+            .execute(&mut *tx).await?;
+
+        // Close any quests still running in the game
+        sqlx::query!("UPDATE quests SET completed = TRUE WHERE game_id = ? AND completed = FALSE", game_id)
+            .execute(&mut *tx).await?;
+
+        // Place the winners in the order they won, then the rest by their confirmed cards
+        let confirmed = CardState::Confirmed as i64;
+        sqlx::query!("
+            INSERT INTO game_standings (game_id, user_id, place, won, confirmed_cards)
+            SELECT game_id, user_id, RANK() OVER (ORDER BY winner_id IS NULL, winner_id, confirmed_cards DESC), 
+                winner_id IS NOT NULL, confirmed_cards
+            FROM (
+                SELECT us.game_id, us.user_id, gw.id AS winner_id,
+                    (SELECT COUNT(*) FROM user_cards uc 
+                     WHERE uc.game_id = us.game_id AND uc.user_id = us.user_id AND uc.state = ?) AS confirmed_cards
+                FROM user_states us
+                LEFT JOIN game_winners gw ON gw.game_id = us.game_id AND gw.user_id = us.user_id
+                WHERE us.game_id = ?
+            )
+        ", confirmed, game_id).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_standings(&self, game_id: i64) -> Result<Vec<StandingModel>> {
+        Ok(
+            sqlx::query_as!(StandingModel, "
+                SELECT gs.place, gs.user_id, u.card_idx, gs.won, gs.confirmed_cards
+                FROM game_standings gs JOIN users u ON gs.user_id = u.id
+                WHERE gs.game_id = ?
+                ORDER BY gs.place ASC, gs.user_id ASC
+            ", game_id).fetch_all(&self.db).await?
+        )
+    }
+
+    async fn reset_game<'a>(&self, game_id: i64, base_stats: &'a BaseStats) -> Result<()> {
+        let mut tx = self.db.begin().await?;
 
+        // Delete the stats of the game's monsters, cascading to their monster states
+        sqlx::query!("
+            DELETE FROM stats WHERE id IN (
+                SELECT ms.stats_id FROM monster_states ms 
+                JOIN quests q ON ms.quest_id = q.id 
+                WHERE q.game_id = ?
+            )
+        ", game_id).execute(&mut *tx).await?;
+
+        // Keep the game's players, so they can still view it, but restore their stats and progress
+        sqlx::query!("
+            UPDATE stats SET health = ?, armor = ?, power = 1, missing_next_turn = FALSE
+            WHERE id IN (SELECT stats_id FROM user_states WHERE game_id = ?)
+        ", base_stats.health, base_stats.armor, game_id).execute(&mut *tx).await?;
+        sqlx::query!("
+            UPDATE user_states SET lvl = 1, xp = 0, riddle_quest_completed = FALSE, exhausted = FALSE, 
+                guessed_today = FALSE, hints_used_today = 0, battles_won_today = 0, trades_today = 0
+            WHERE game_id = ?
+        ", game_id).execute(&mut *tx).await?;
+
+        // Delete the game's quests and trades, the cards players collected and their guesses
+        sqlx::query!("DELETE FROM quests WHERE game_id = ?", game_id)
+            .execute(&mut *tx).await?;
+        sqlx::query!("DELETE FROM card_trades WHERE game_id = ?", game_id)
+            .execute(&mut *tx).await?;
+        sqlx::query!("DELETE FROM user_cards WHERE game_id = ?", game_id)
+            .execute(&mut *tx).await?;
+        sqlx::query!("DELETE FROM target_guesses WHERE game_id = ?", game_id)
+            .execute(&mut *tx).await?;
+
+        tx.commit().await?;
         Ok(())
     }
 
-    async fn game_state(&self, user_id: i64) -> Result<Option<GameStateModel>> {
-        // Get the game state from the database (may not yet exist)
-        let murdered_user_id = sqlx::query!("SELECT murdered_user_id FROM game_states")
-            .fetch_optional(&self.db).await?
-            .and_then(|row| Some(row.murdered_user_id));
+    async fn winner_limit_reached(&self, game_id: i64) -> Result<bool> {
+        let game = sqlx::query!(r#"
+            SELECT winner_limit, (SELECT COUNT(*) FROM game_winners WHERE game_id = gs.id) AS "winner_count!: i64"
+            FROM game_states gs WHERE id = ?
+            "#, game_id
+        ).fetch_one(&self.db).await?;
+
+        Ok(game.winner_limit.is_some_and(|limit| game.winner_count >= limit))
+    }
+
+    async fn get_archived_game(&self, game_id: i64) -> Result<Option<ArchivedGameModel>> {
+        let game = sqlx::query!(r#"
+            SELECT id, name, created_on, archived_on AS "archived_on!", murdered_user_id
+            FROM game_states WHERE id = ? AND archived_on IS NOT NULL
+            "#, game_id
+        ).fetch_optional(&self.db).await?;
+
+        let Some(game) = game else { return Ok(None) };
+
+        Ok(Some(ArchivedGameModel {
+            id: game.id,
+            name: game.name,
+            created_on: game.created_on,
+            archived_on: game.archived_on,
+            murdered_user_id: game.murdered_user_id,
+            target_cards: self.get_target_cards(game_id).await?,
+            winner_idxs: self.get_winner_idxs(game_id).await?,
+        }))
+    }
+
+    async fn game_state(&self, game_id: i64, user_id: i64) -> Result<Option<GameStateModel>> {
+        // Get the game state from the database (may not exist)
+        let game = sqlx::query!("SELECT murdered_user_id, deadline, partial_feedback, archived_on FROM game_states WHERE id = ?", game_id)
+            .fetch_optional(&self.db).await?;
 
         // If the game state does not exist, return None
-        if murdered_user_id.is_none() {
-            return Ok(None);
-        }
+        let Some(game) = game else { return Ok(None) };
 
-        // Get the user's info
+        // Get the user's info, and their daily state in the game
         let user = sqlx::query!("
-            SELECT exhausted, riddle_quest_completed, guessed_today, last_login FROM users WHERE id = ?
-            ", user_id
+            SELECT us.lvl, us.xp, us.exhausted, us.riddle_quest_completed, us.guessed_today, u.last_login 
+            FROM users u JOIN user_states us ON us.user_id = u.id
+            WHERE us.game_id = ? AND u.id = ?
+            ", game_id, user_id
         ).fetch_one(&self.db).await?;
         
         // Determine if the given user has won the game
-        let has_won = sqlx::query!("SELECT * FROM game_winners WHERE user_id = ?", user_id)
+        let has_won = sqlx::query!("SELECT * FROM game_winners WHERE game_id = ? AND user_id = ?", game_id, user_id)
             .fetch_optional(&self.db).await?.is_some();
 
-        // If the user has won, retrieve the target cards and all current winners
+        // If the user has won or the game has ended, retrieve the target cards and all current winners
         let (mut target_cards, mut winner_idxs) = (None, None);
 
-        if has_won {
-            target_cards = Some(
-                sqlx::query_as!(CardModel,
-                    "SELECT cat_idx, card_idx FROM game_target_cards ORDER BY cat_idx ASC"
-                ).fetch_all(&self.db).await?
-            );
-            
-            winner_idxs = Some(
-                sqlx::query!("
-                    SELECT u.card_idx FROM game_winners gw 
-                    JOIN users u ON gw.user_id = u.id 
-                    ORDER BY gw.id ASC
-                ")
-                    .fetch_all(&self.db).await?
-                    .iter().map(|row| row.card_idx).collect::<Vec<i64>>()
-            );
+        if has_won || game.archived_on.is_some() {
+            let mut cards = self.get_target_cards(game_id).await?;
+            cards.sort_by_key(|card| card.cat_idx);
+            target_cards = Some(cards);
+            winner_idxs = Some(self.get_winner_idxs(game_id).await?);
         }
 
-        // Get the user's current guessed cards and confirmed cards
-        let user_cards = sqlx::query_as!(UserCardModel,
-            "SELECT cat_idx, card_idx, confirmed FROM user_cards WHERE user_id = ?", 
-            user_id
-        ).fetch_all(&self.db).await?;
+        // Once the game has ended, retrieve the final standings
+        let standings = match game.archived_on {
+            Some(_) => Some(self.get_standings(game_id).await?),
+            None => None,
+        };
 
         let user_stats = sqlx::query_as!(Stats,
-            "SELECT power, health, armor, missing_next_turn as miss_turn FROM stats WHERE id = ?", 
-            user_id
+            "SELECT s.power, s.health, s.armor, s.missing_next_turn as miss_turn
+            FROM user_states us JOIN stats s ON us.stats_id = s.id
+            WHERE us.game_id = ? AND us.user_id = ?",
+            game_id, user_id
         )
             .fetch_one(&self.db).await?;
 
@@ -180,12 +434,22 @@ impl GameDataLayer for DbGameDataLayer {
             .execute(&self.db).await?;
 
         Ok(Some(GameStateModel {
+            game_id,
             user_id,
             target_cards, 
-            user_cards, 
+            notebook: Vec::new(),
+            achievements: Vec::new(),
             user_stats,
+            pl_lvl: user.lvl,
+            pl_xp: user.xp,
+            pl_xp_to_next_lvl: None,
             winner_idxs,
-            murdered_user_id: murdered_user_id.unwrap(),
+            standings,
+            deadline: game.deadline,
+            secs_to_deadline: None,
+            partial_feedback: game.partial_feedback,
+            pl_guesses: self.get_target_guesses(game_id, user_id).await?,
+            murdered_user_id: game.murdered_user_id,
             pl_exhausted: user.exhausted,
             pl_completed_daily_riddle: user.riddle_quest_completed,
             pl_completed_all_riddles: false,
@@ -194,94 +458,304 @@ impl GameDataLayer for DbGameDataLayer {
         }))
     }
 
-    async fn get_target_cards(&self) -> Result<Vec<CardModel>> {
+    async fn get_target_cards(&self, game_id: i64) -> Result<Vec<CardModel>> {
         Ok(
             sqlx::query_as!(CardModel, 
-                "SELECT cat_idx, card_idx FROM game_target_cards"
+                "SELECT cat_idx, card_idx FROM game_target_cards WHERE game_id = ?", game_id
             ).fetch_all(&self.db).await?
         )
     }
 
-    async fn add_new_winner(&self, user_id: i64) -> Result<()> {
-        sqlx::query!("INSERT INTO game_winners (user_id) VALUES (?)", user_id)
-            .execute(&self.db).await?;
+    async fn has_partial_feedback(&self, game_id: i64) -> Result<bool> {
+        Ok(
+            sqlx::query!("SELECT partial_feedback FROM game_states WHERE id = ?", game_id)
+                .fetch_one(&self.db).await?.partial_feedback
+        )
+    }
+
+    async fn add_target_guess<'a>(&self, game_id: i64, user_id: i64, guess: &'a [CardModel], correct: bool, correct_cats: Option<i64>) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+
+        let guess_id = sqlx::query!(
+            "INSERT INTO target_guesses (game_id, user_id, correct, correct_cats) VALUES (?, ?, ?, ?)",
+            game_id, user_id, correct, correct_cats
+        ).execute(&mut *tx).await?.last_insert_rowid();
+
+        for card in guess {
+            sqlx::query!(
+                "INSERT INTO target_guess_cards (guess_id, cat_idx, card_idx) VALUES (?, ?, ?)",
+                guess_id, card.cat_idx, card.card_idx
+            ).execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
         Ok(())
     }
 
-    async fn update_user_card(&self, user_id: i64, cat_idx: i64, card_idx: i64, guessed: bool) -> Result<()> {
-
-        // Get the current value of the choice card
-        let confirmed = sqlx::query!(
-            "SELECT confirmed FROM user_cards WHERE user_id = ? AND cat_idx = ? AND card_idx = ?",
-            user_id, cat_idx, card_idx
-        ).fetch_optional(&self.db).await?.and_then(|row| Some(row.confirmed));
-
-        match confirmed {
-            // If there's no user card that matches and the user has guessed, insert
-            // that card, unconfirmed
-            None if guessed => {
-                sqlx::query!(
-                    "INSERT INTO user_cards (user_id, cat_idx, card_idx) VALUES (?, ?, ?)", 
-                    user_id, cat_idx, card_idx
-                ).execute(&self.db).await?;
-            },
-            // If a card does exist, is unconfirmed, and the user no longer guesses it,
-            // delete that card
-            Some(false) if !guessed => {
-                sqlx::query!(
-                    "DELETE FROM user_cards WHERE user_id = ? AND cat_idx = ? AND card_idx = ?",
-                    user_id, cat_idx, card_idx
-                ).execute(&self.db).await?;
-            },
-            // Ignore any other cases - they do not affect the user card
-            // (card exists and is guessed doesn't matter, as there is already a card representing this)
-            // (card confirmed but no longer guessed doesn't matter, as the card is confirmed)
-            _ => {}
+    async fn get_target_guesses(&self, game_id: i64, user_id: i64) -> Result<Vec<TargetGuessModel>> {
+        let rows = sqlx::query!(r#"
+            SELECT tg.id, tg.correct, tg.correct_cats, tg.created_on, tgc.cat_idx AS "cat_idx?", tgc.card_idx AS "card_idx?"
+            FROM target_guesses tg
+            LEFT JOIN target_guess_cards tgc ON tgc.guess_id = tg.id
+            WHERE tg.game_id = ? AND tg.user_id = ?
+            ORDER BY tg.id ASC, tgc.cat_idx ASC
+            "#, game_id, user_id
+        ).fetch_all(&self.db).await?;
+
+        // Group the cards of each guess, which are in category order
+        let mut guesses: Vec<(i64, TargetGuessModel)> = Vec::new();
+        for row in rows {
+            if guesses.last().is_none_or(|(id, _)| *id != row.id) {
+                guesses.push((row.id, TargetGuessModel {
+                    cards: Vec::new(),
+                    correct: row.correct,
+                    correct_cats: row.correct_cats,
+                    created_on: row.created_on,
+                }));
+            }
+            if let (Some(cat_idx), Some(card_idx), Some((_, guess))) = (row.cat_idx, row.card_idx, guesses.last_mut()) {
+                guess.cards.push(CardModel { cat_idx, card_idx });
+            }
         }
 
+        Ok(guesses.into_iter().map(|(_, guess)| guess).collect())
+    }
+
+    async fn add_new_winner(&self, game_id: i64, user_id: i64) -> Result<()> {
+        sqlx::query!("INSERT INTO game_winners (game_id, user_id) VALUES (?, ?)", game_id, user_id)
+            .execute(&self.db).await?;
         Ok(())
     }
 
-    async fn get_confirmed_user_cards(&self, user_id: i64) -> Result<Vec<CardModel>> {
+    async fn get_notebook(&self, game_id: i64, user_id: i64) -> Result<Vec<NotebookCardEntity>> {
         Ok(
-            sqlx::query_as!(CardModel,
-                "SELECT cat_idx, card_idx FROM user_cards WHERE user_id = ? AND confirmed = TRUE",
-                user_id
-            )
-                .fetch_all(&self.db).await?
+            sqlx::query_as!(NotebookCardEntity, r#"
+                SELECT uc.cat_idx, uc.card_idx, uc.state, uc.note, uc.source_quest_id, q.quest_type AS "source_quest_type?", uc.source_trade_id
+                FROM user_cards uc
+                LEFT JOIN quests q ON uc.source_quest_id = q.id
+                WHERE uc.game_id = ? AND uc.user_id = ?
+                ORDER BY uc.cat_idx ASC, uc.card_idx ASC
+                "#, game_id, user_id
+            ).fetch_all(&self.db).await?
         )
     }
 
-    async fn confirm_user_card(&self, user_id: i64, cat_idx: i64, card_idx: i64) -> Result<()> {
-        // If the card already exists in the users evidence cards, update it to confirmed
-        let card = sqlx::query_as!(CardModel,
-            "SELECT cat_idx, card_idx FROM user_cards 
-            WHERE user_id = ? AND cat_idx = ? AND card_idx = ?",
-            user_id, cat_idx, card_idx
-        ).fetch_optional(&self.db).await?;
-
-        if let Some(card) = card {
-            sqlx::query!(
-                "UPDATE user_cards SET confirmed = TRUE WHERE cat_idx = ? AND card_idx = ?",
-                card.cat_idx, card.card_idx
-            ).execute(&self.db).await?;
-        // Otherwise, create a new user card as confirmed
-        } else {
-            sqlx::query!(
-                "INSERT INTO user_cards (user_id, cat_idx, card_idx, confirmed) VALUES (?, ?, ?, TRUE)",
-                user_id, cat_idx, card_idx
-            ).execute(&self.db).await?;
+    async fn update_notebook<'a>(&self, game_id: i64, user_id: i64, updates: &'a [NotebookUpdateModel]) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        let confirmed = CardState::Confirmed as i64;
+
+        for update in updates {
+            // Confirmed cards keep their state, only taking the new note
+            let updated_confirmed = sqlx::query!(
+                "UPDATE user_cards SET note = ? WHERE game_id = ? AND user_id = ? AND cat_idx = ? AND card_idx = ? AND state = ?",
+                update.note, game_id, user_id, update.cat_idx, update.card_idx, confirmed
+            ).execute(&mut *tx).await?.rows_affected() > 0;
+
+            if updated_confirmed {
+                continue;
+            }
+
+            match (update.state, &update.note) {
+                // An unconfirmed card with nothing noted of it is removed
+                (None, None) => {
+                    sqlx::query!(
+                        "DELETE FROM user_cards WHERE game_id = ? AND user_id = ? AND cat_idx = ? AND card_idx = ?",
+                        game_id, user_id, update.cat_idx, update.card_idx
+                    ).execute(&mut *tx).await?;
+                },
+                // Otherwise, the card is added or replaced, suspected by default
+                (state, note) => {
+                    let state = state.unwrap_or(CardState::Suspected) as i64;
+                    sqlx::query!("
+                        INSERT INTO user_cards (game_id, user_id, cat_idx, card_idx, state, note) VALUES (?, ?, ?, ?, ?, ?)
+                        ON CONFLICT (game_id, user_id, cat_idx, card_idx) DO UPDATE SET state = excluded.state, note = excluded.note
+                        ", game_id, user_id, update.cat_idx, update.card_idx, state, note
+                    ).execute(&mut *tx).await?;
+                },
+            }
         }
 
+        tx.commit().await?;
         Ok(())
-    } 
+    }
 
-    async fn get_completed_riddle_count(&self, user_id: i64) -> Result<i64> {
+    async fn confirm_user_card(&self, game_id: i64, user_id: i64, cat_idx: i64, card_idx: i64, source: Option<CardSource>) -> Result<()> {
+        confirm_card(&self.db, game_id, user_id, cat_idx, card_idx, source).await
+    }
+
+    async fn is_card_confirmed<'a>(&self, game_id: i64, user_id: i64, card: &'a CardModel) -> Result<bool> {
+        let confirmed = CardState::Confirmed as i64;
         Ok(
             sqlx::query!(
-                "SELECT COUNT(*) AS count FROM quests WHERE quest_type = ? AND completed = TRUE AND user_id = ?",
-                2, user_id
+                "SELECT state FROM user_cards WHERE game_id = ? AND user_id = ? AND cat_idx = ? AND card_idx = ? AND state = ?",
+                game_id, user_id, card.cat_idx, card.card_idx, confirmed
+            ).fetch_optional(&self.db).await?.is_some()
+        )
+    }
+
+    async fn get_pl_trades_today(&self, game_id: i64, user_id: i64) -> Result<i64> {
+        Ok(
+            sqlx::query!("SELECT trades_today FROM user_states WHERE game_id = ? AND user_id = ?", game_id, user_id)
+                .fetch_one(&self.db).await?.trades_today
+        )
+    }
+
+    async fn create_trade<'a>(&self, game_id: i64, from_user_id: i64, to_user_id: i64, card: &'a CardModel) -> Result<i64> {
+        Ok(
+            sqlx::query!("
+                INSERT INTO card_trades (game_id, from_user_id, to_user_id, offered_cat_idx, offered_card_idx) VALUES (?, ?, ?, ?, ?)
+                ", game_id, from_user_id, to_user_id, card.cat_idx, card.card_idx
+            ).execute(&self.db).await?.last_insert_rowid()
+        )
+    }
+
+    async fn get_trade(&self, game_id: i64, trade_id: i64) -> Result<Option<TradeEntity>> {
+        Ok(
+            sqlx::query_as!(TradeEntity, "
+                SELECT id, from_user_id, to_user_id, offered_cat_idx, offered_card_idx, returned_cat_idx, 
+                    returned_card_idx, status, created_on, resolved_on
+                FROM card_trades WHERE game_id = ? AND id = ?
+                ", game_id, trade_id
+            ).fetch_optional(&self.db).await?
+        )
+    }
+
+    async fn get_trades(&self, game_id: i64, user_id: i64) -> Result<Vec<TradeEntity>> {
+        Ok(
+            sqlx::query_as!(TradeEntity, "
+                SELECT id, from_user_id, to_user_id, offered_cat_idx, offered_card_idx, returned_cat_idx, 
+                    returned_card_idx, status, created_on, resolved_on
+                FROM card_trades WHERE game_id = ? AND (from_user_id = ? OR to_user_id = ?)
+                ORDER BY id DESC
+                ", game_id, user_id, user_id
+            ).fetch_all(&self.db).await?
+        )
+    }
+
+    async fn resolve_trade<'a>(&self, trade_id: i64, status: TradeStatus, returned_card: Option<&'a CardModel>) -> Result<bool> {
+        let (status, pending) = (status as i64, TradeStatus::Pending as i64);
+        let (returned_cat_idx, returned_card_idx) = (returned_card.map(|card| card.cat_idx), returned_card.map(|card| card.card_idx));
+        let now = Utc::now().naive_utc();
+        Ok(
+            sqlx::query!("
+                UPDATE card_trades SET status = ?, returned_cat_idx = ?, returned_card_idx = ?, resolved_on = ?
+                WHERE id = ? AND status = ?
+                ", status, returned_cat_idx, returned_card_idx, now, trade_id, pending
+            ).execute(&self.db).await?.rows_affected() > 0
+        )
+    }
+
+    async fn accept_trade<'a>(&self, game_id: i64, trade: &'a TradeModel, returned_card: &'a CardModel, daily_trade_limit: i64) -> Result<TradeAcceptance> {
+        let mut tx = self.db.begin().await?;
+        let (accepted, pending) = (TradeStatus::Accepted as i64, TradeStatus::Pending as i64);
+        let now = Utc::now().naive_utc();
+
+        // Resolve the trade first, so it can only be accepted once
+        let resolved = sqlx::query!("
+            UPDATE card_trades SET status = ?, returned_cat_idx = ?, returned_card_idx = ?, resolved_on = ?
+            WHERE id = ? AND status = ?
+            ", accepted, returned_card.cat_idx, returned_card.card_idx, now, trade.id, pending
+        ).execute(&mut *tx).await?.rows_affected() > 0;
+        if !resolved {
+            return Ok(TradeAcceptance::NotPending);
+        }
+
+        // Count the trade for both players, rolling back if either has reached their limit
+        for user_id in [trade.from_user_id, trade.to_user_id] {
+            let counted = sqlx::query!("
+                UPDATE user_states SET trades_today = trades_today + 1 
+                WHERE game_id = ? AND user_id = ? AND trades_today < ?
+                ", game_id, user_id, daily_trade_limit
+            ).execute(&mut *tx).await?.rows_affected() > 0;
+            if !counted {
+                return Ok(TradeAcceptance::LimitReached);
+            }
+        }
+
+        // Each player confirms the card they received
+        let source = Some(CardSource::Trade(trade.id));
+        confirm_card(&mut *tx, game_id, trade.to_user_id, trade.offered_card.cat_idx, trade.offered_card.card_idx, source).await?;
+        confirm_card(&mut *tx, game_id, trade.from_user_id, returned_card.cat_idx, returned_card.card_idx, source).await?;
+
+        tx.commit().await?;
+        Ok(TradeAcceptance::Accepted)
+    }
+
+    async fn get_solved_riddle_count(&self, game_id: i64, user_id: i64) -> Result<i64> {
+        let riddle_type = QuestKind::Riddle as i64;
+        Ok(
+            sqlx::query!(r#"
+                SELECT COUNT(DISTINCT qr.riddle_idx) AS "count!: i64" 
+                FROM quests q JOIN quest_riddles qr ON q.id = qr.quest_id
+                WHERE q.game_id = ? AND q.user_id = ? AND q.quest_type = ? AND q.succeeded = TRUE
+                "#, game_id, user_id, riddle_type
             ).fetch_one(&self.db).await?.count
         )
     }
-}
\ No newline at end of file
+
+    async fn get_leaderboard(&self, game_id: i64) -> Result<Vec<LeaderboardEntryModel>> {
+        let (confirmed, monster_type, riddle_type) = (CardState::Confirmed as i64, QuestKind::Monster as i64, QuestKind::Riddle as i64);
+        Ok(
+            sqlx::query_as!(LeaderboardEntryModel, r#"
+                SELECT us.user_id, u.card_idx,
+                    (SELECT COUNT(*) FROM user_cards uc 
+                     WHERE uc.game_id = us.game_id AND uc.user_id = us.user_id AND uc.state = ?) AS "confirmed_cards!: i64",
+                    (SELECT COUNT(*) FROM quests q 
+                     WHERE q.game_id = us.game_id AND q.user_id = us.user_id AND q.succeeded = TRUE) AS "quests_completed!: i64",
+                    (SELECT COUNT(*) FROM quests q 
+                     WHERE q.game_id = us.game_id AND q.user_id = us.user_id AND q.succeeded = TRUE AND q.quest_type = ?) AS "monsters_defeated!: i64",
+                    (SELECT COUNT(*) FROM quests q 
+                     WHERE q.game_id = us.game_id AND q.user_id = us.user_id AND q.succeeded = TRUE AND q.quest_type = ?) AS "riddles_solved!: i64",
+                    CASE WHEN gw.id IS NULL THEN NULL ELSE (
+                        SELECT COUNT(*) FROM game_winners w WHERE w.game_id = gw.game_id AND w.id <= gw.id
+                    ) END AS "win_place?: i64"
+                FROM user_states us
+                JOIN users u ON us.user_id = u.id
+                LEFT JOIN game_winners gw ON gw.game_id = us.game_id AND gw.user_id = us.user_id
+                WHERE us.game_id = ?
+                ORDER BY us.user_id ASC
+                "#, confirmed, monster_type, riddle_type, game_id
+            ).fetch_all(&self.db).await?
+        )
+    }
+}
+
+impl DbGameDataLayer {
+    ///
+    /// Retrieves the person card indices of the winners of the game, in the order they won
+    ///
+    async fn get_winner_idxs(&self, game_id: i64) -> Result<Vec<i64>> {
+        Ok(
+            sqlx::query!("
+                SELECT u.card_idx FROM game_winners gw
+                JOIN users u ON gw.user_id = u.id
+                WHERE gw.game_id = ?
+                ORDER BY gw.id ASC
+            ", game_id)
+                .fetch_all(&self.db).await?
+                .iter().map(|row| row.card_idx).collect::<Vec<i64>>()
+        )
+    }
+}
+
+///
+/// Confirms the card for the user in the game, revealed by the given `source`, if any,
+/// keeping any note the user had made of it
+/// 
+async fn confirm_card<'e, E: Executor<'e, Database = Sqlite>>(executor: E, game_id: i64, user_id: i64, cat_idx: i64, card_idx: i64, source: Option<CardSource>) -> Result<()> {
+    let (source_quest_id, source_trade_id) = match source {
+        Some(CardSource::Quest(quest_id)) => (Some(quest_id), None),
+        Some(CardSource::Trade(trade_id)) => (None, Some(trade_id)),
+        None => (None, None),
+    };
+
+    let confirmed = CardState::Confirmed as i64;
+    sqlx::query!("
+        INSERT INTO user_cards (game_id, user_id, cat_idx, card_idx, state, source_quest_id, source_trade_id) VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (game_id, user_id, cat_idx, card_idx) DO UPDATE 
+        SET state = excluded.state, source_quest_id = excluded.source_quest_id, source_trade_id = excluded.source_trade_id
+        ", game_id, user_id, cat_idx, card_idx, confirmed, source_quest_id, source_trade_id
+    ).execute(executor).await?;
+
+    Ok(())
+}
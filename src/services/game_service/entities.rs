@@ -0,0 +1,67 @@
+use chrono::NaiveDateTime;
+
+use super::models::CardModel;
+
+///
+/// A card in a player's notebook, as stored. The state is a `CardState` discriminant, 
+/// and the source quest's type a `QuestKind` discriminant
+/// 
+pub struct NotebookCardEntity {
+    pub cat_idx: i64,
+    pub card_idx: i64,
+    pub state: i64,
+    pub note: Option<String>,
+    pub source_quest_id: Option<i64>,
+    pub source_quest_type: Option<i64>,
+    pub source_trade_id: Option<i64>,
+}
+
+///
+/// A card trade between two players, as stored. The status is a `TradeStatus` discriminant, 
+/// and the returned card is only set once accepted
+/// 
+pub struct TradeEntity {
+    pub id: i64,
+    pub from_user_id: i64,
+    pub to_user_id: i64,
+    pub offered_cat_idx: i64,
+    pub offered_card_idx: i64,
+    pub returned_cat_idx: Option<i64>,
+    pub returned_card_idx: Option<i64>,
+    pub status: i64,
+    pub created_on: NaiveDateTime,
+    pub resolved_on: Option<NaiveDateTime>,
+}
+
+///
+/// A player of a game being set up, either an existing user, or a new user to
+/// create alongside the game with their already hashed password
+/// 
+pub enum GamePlayerEntity {
+    Existing(i64),
+    New { email: String, pwd_hash: String, card_idx: i64 },
+}
+
+///
+/// A game to create, with its players. The murdered player is the one at
+/// index `murdered_player` of `players`
+/// 
+pub struct NewGameEntity {
+    pub name: String,
+    pub winner_limit: Option<i64>,
+    pub deadline: Option<NaiveDateTime>,
+    pub partial_feedback: bool,
+    pub target_cards: Vec<CardModel>,
+    pub players: Vec<GamePlayerEntity>,
+    pub murdered_player: usize,
+}
+
+///
+/// The outcome of accepting a trade. A trade is only accepted while pending, and
+/// while neither player has reached their daily trade limit
+/// 
+pub enum TradeAcceptance {
+    Accepted,
+    NotPending,
+    LimitReached,
+}
@@ -2,7 +2,7 @@ use axum::{response::{IntoResponse, Response}, http::StatusCode};
 use log::error;
 use thiserror::Error;
 
-use crate::{data_layer_error::DataLayerError, services::auth_service::error::AuthServiceError};
+use crate::{data_layer_error::DataLayerError, services::{achievement_service::error::AchievementServiceError, auth_service::error::AuthServiceError}};
 
 pub type Result<T> = std::result::Result<T, GameServiceError>;
 
@@ -12,14 +12,56 @@ pub enum GameServiceError {
     AuthServiceError(AuthServiceError),
     #[error("Internal server error")]
     DataLayerError(DataLayerError),
-    #[error("Game already running")]
-    GameAlreadyRunning,
+    #[error("Internal server error")]
+    AchievementServiceError(AchievementServiceError),
     #[error("Game is not running")]
     GameNotRunning,
+    #[error("Game has not ended")]
+    GameNotEnded,
+    #[error("User is not a player in this game")]
+    NotInGame,
+    #[error("User has already joined this game")]
+    AlreadyInGame,
+    #[error("User {0} does not exist")]
+    UserNotFound(i64),
     #[error("Out of range of categories or cards. Please check your range and try again.")]
     GuessOutOfRange,
+    #[error("No category has the tag '{0}'")]
+    UnknownCategory(String),
+    #[error("Guess is missing a card for the category '{0}'")]
+    MissingCategory(String),
+    #[error("Unknown card state `{0}`")]
+    UnknownCardState(i64),
+    #[error("Cards can only be confirmed by the game")]
+    CannotConfirmCard,
+    #[error("Unknown trade status `{0}`")]
+    UnknownTradeStatus(i64),
+    #[error("Only confirmed cards can be traded")]
+    CardNotConfirmed,
+    #[error("Target cards can never be traded")]
+    TargetCardNotTradeable,
+    #[error("Players cannot trade with themselves")]
+    CannotTradeWithSelf,
+    #[error("Trade not found")]
+    TradeNotFound,
+    #[error("Trade has already been resolved")]
+    TradeNotPending,
+    #[error("Daily trade limit reached")]
+    DailyTradeLimitReached,
     #[error("Users must be initialized to set up game")]
-    UsersNotFound
+    UsersNotFound,
+    #[error("No person card exists at index {0}")]
+    PersonCardOutOfRange(i64),
+    #[error("More than one player plays as the person card at index {0}")]
+    DuplicatePersonCard(i64),
+    #[error("More than one player is registering with the email '{0}'")]
+    DuplicateEmail(String),
+    #[error("No player plays as the person card at index {0}")]
+    MurderedPlayerNotFound(i64),
+    #[error("The game's deadline must be in the future")]
+    DeadlineInPast,
+    #[error("No person card besides the murdered player's is left to be the murderer")]
+    NoSuspectsAvailable,
 }
 
 impl Into<GameServiceError> for DataLayerError {
@@ -34,6 +76,12 @@ impl Into<GameServiceError> for AuthServiceError {
     }
 }
 
+impl Into<GameServiceError> for AchievementServiceError {
+    fn into(self) -> GameServiceError {
+        GameServiceError::AchievementServiceError(self)
+    }
+}
+
 impl IntoResponse for GameServiceError {
     fn into_response(self) -> Response {
         println!("{:?}", self);
@@ -41,6 +89,9 @@ impl IntoResponse for GameServiceError {
             GameServiceError::DataLayerError(e) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "An internal server error occured").into_response()
             },
+            GameServiceError::AchievementServiceError(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "An internal server error occured").into_response()
+            },
             _ => (StatusCode::BAD_REQUEST, self.to_string()).into_response()
         }
     }
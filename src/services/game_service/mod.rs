@@ -1,19 +1,28 @@
 pub mod error;
 pub mod data_layer;
+pub mod entities;
 pub mod models;
+pub mod settings;
 
-use std::sync::Arc;
+use std::{collections::{HashMap, HashSet}, sync::Arc};
 
 use axum::async_trait;
+use chrono::Utc;
 use derive_more::Constructor;
+use log::error;
 use models::GuessResult;
 use rand::{seq::IteratorRandom, rngs::StdRng, SeedableRng};
 
-use crate::resources::game_resources::Resources;
+use crate::{resources::game_resources::Resources, services::quest_service::models::QuestKind};
 
-use self::{error::{GameServiceError, Result}, data_layer::GameDataLayer, models::{CardModel, GameInitialStateModel, GameStateModel}};
+use self::{error::{GameServiceError, Result}, data_layer::GameDataLayer, entities::{GamePlayerEntity, NewGameEntity, TradeAcceptance, TradeEntity}, models::{ArchivedGameModel, CardModel, CardSource, CardSourceModel, CardState, GameInitialStateModel, GameModel, GameSetupModel, GameStateModel, LeaderboardEntryModel, NotebookCardModel, NotebookUpdateModel, TradeModel, TradeOfferModel, TradeStatus}, settings::GameSettings};
 
-use super::auth_service::AuthService;
+use super::{achievement_service::{models::GameEvent, AchievementService}, auth_service::AuthService};
+
+///
+/// Index of the category of person cards, which players play as
+/// 
+pub const PERSON_CAT_IDX: usize = 0;
 
 ///
 /// Service which interacts with the endgame components
@@ -24,31 +33,92 @@ use super::auth_service::AuthService;
 #[async_trait]
 pub trait GameService : Send + Sync {
     ///
-    /// Establishes a new game, if one does not already exist.
-    /// Returns `GameServiceError::GameAlreadyRunning` if game has
-    /// already been set up
+    /// Establishes a new game with the given `setup`, alongside any games already running.
+    /// Returns the new game's id, murdered user and target cards
+    /// 
+    async fn setup_game(&self, setup: GameSetupModel) -> Result<GameInitialStateModel>;
+    ///
+    /// Retrieves every game, noting which the user with the given `user_id` has joined
+    /// 
+    async fn get_games(&self, user_id: i64) -> Result<Vec<GameModel>>;
+    ///
+    /// Adds the user with the given `user_id` as a player in the game with the given `game_id`.
+    /// Throws Error if the game is not running, or the user has already joined
+    /// 
+    async fn join_game(&self, game_id: i64, user_id: i64) -> Result<()>;
+    ///
+    /// Ends the game with the given `game_id`, archiving it with its winners and target cards
+    /// 
+    async fn end_game(&self, game_id: i64) -> Result<ArchivedGameModel>;
+    ///
+    /// Reveals the target cards and winners of the ended game with the given `game_id`
+    /// 
+    async fn reveal_game(&self, game_id: i64) -> Result<ArchivedGameModel>;
+    ///
+    /// Clears the player state of the ended game with the given `game_id`, keeping
+    /// only its players, target cards and winners
+    /// 
+    async fn reset_game(&self, game_id: i64) -> Result<()>;
+    ///
+    /// Ensures the game with the given `game_id` is running, and the user 
+    /// with the given `user_id` is one of its players
+    /// 
+    async fn ensure_pl_in_game(&self, game_id: i64, user_id: i64) -> Result<()>;
+    ///
+    /// Ensures the user with the given `user_id` is or was one of the players of
+    /// the game with the given `game_id`, whether it's running, ended or archived
     /// 
-    async fn setup_game(&self,) -> Result<GameInitialStateModel>;
+    async fn ensure_pl_was_in_game(&self, game_id: i64, user_id: i64) -> Result<()>;
     ///
     /// Retrieves the state of the game, including user-specific 
     /// state.
     /// 
-    async fn game_state<'a>(&self, usr_id: i64) -> Result<GameStateModel>;
+    async fn game_state<'a>(&self, game_id: i64, usr_id: i64) -> Result<GameStateModel>;
     ///
-    /// Allows the user to guess the target cards with the provided slice of `i64`s.
-    /// Returns `true` if the cards are correctly guessed, `false` otherwise.
-    /// Guesses are in order of category ID (ie. the first index must be for the 
-    /// first category, etc.)
+    /// Allows the user to guess the target cards, with one card index for every category, 
+    /// keyed by the category's tag. Throws Error if any category is unknown, missing,
+    /// or its card index is out of range
     /// 
-    async fn guess_target_cards<'a>(&self, user_id: i64, guess: &'a [i64]) -> Result<GuessResult>;
+    async fn guess_target_cards<'a>(&self, game_id: i64, user_id: i64, guess: &'a HashMap<String, i64>) -> Result<GuessResult>;
     ///
-    /// Updates a user's card state with the particular guess-decision of the card specified.
+    /// Retrieves the user's notebook for the game, of every card they have noted or had confirmed
     /// 
-    async fn update_user_card(&self, user_id: i64, cat_idx: i64, card_idx: i64, guessed: bool) -> Result<()>;
+    async fn get_notebook(&self, game_id: i64, user_id: i64) -> Result<Vec<NotebookCardModel>>;
     ///
-    /// Confirms a card for the given `user_id`, with specific `cat_idx` and `card_idx`
+    /// Applies the given `updates` to the user's notebook for the game, returning the updated notebook.
+    /// Throws Error if any card is out of range, or would be confirmed by the user
     /// 
-    async fn confirm_user_card(&self, user_id: i64, cat_idx: i64, card_idx: i64) -> Result<()>;
+    async fn update_notebook(&self, game_id: i64, user_id: i64, updates: Vec<NotebookUpdateModel>) -> Result<Vec<NotebookCardModel>>;
+    ///
+    /// Confirms a card for the given `user_id`, with specific `cat_idx` and `card_idx`,
+    /// revealed by the given `source`, if any
+    /// 
+    async fn confirm_user_card(&self, game_id: i64, user_id: i64, cat_idx: i64, card_idx: i64, source: Option<CardSource>) -> Result<()>;
+    ///
+    /// Offers one of the user's confirmed cards to another player in the game, in exchange
+    /// for one of theirs. Throws Error if the card is not confirmed, is a target card, or 
+    /// the user has reached their daily trade limit
+    /// 
+    async fn offer_trade(&self, game_id: i64, user_id: i64, offer: TradeOfferModel) -> Result<TradeModel>;
+    ///
+    /// Accepts the trade with the given `trade_id` offered to the user, returning one of their 
+    /// confirmed cards. Both cards are confirmed for the receiving player
+    /// 
+    async fn accept_trade(&self, game_id: i64, user_id: i64, trade_id: i64, card: CardModel) -> Result<TradeModel>;
+    ///
+    /// Declines the trade with the given `trade_id` offered to the user
+    /// 
+    async fn decline_trade(&self, game_id: i64, user_id: i64, trade_id: i64) -> Result<TradeModel>;
+    ///
+    /// Retrieves the user's history of trades in the game, newest first
+    /// 
+    async fn get_trades(&self, game_id: i64, user_id: i64) -> Result<Vec<TradeModel>>;
+    ///
+    /// Retrieves the progress of every player in the game, for one of its players. Winners
+    /// are ranked by the order they won once the user has won or the game has ended, and the
+    /// rest by their confirmed cards and completed quests
+    /// 
+    async fn get_leaderboard(&self, game_id: i64, user_id: i64) -> Result<Vec<LeaderboardEntryModel>>;
 
 }
 
@@ -59,128 +129,397 @@ pub trait GameService : Send + Sync {
 pub struct DbGameService { 
     data_layer: Arc<dyn GameDataLayer>,
     auth_service: Arc<dyn AuthService>,
-    res: Arc<Resources>
+    achievement_service: Arc<dyn AchievementService>,
+    res: Arc<Resources>,
+    settings: GameSettings,
 }
 
 #[async_trait]
 impl GameService for DbGameService {
-    async fn setup_game(&self) -> Result<GameInitialStateModel> {
+    async fn setup_game(&self, setup: GameSetupModel) -> Result<GameInitialStateModel> {
         let mut rng = StdRng::from_entropy();
 
-        // Ensure there is no game actively running
-        if self.data_layer.is_game_active().await.map_err(|e| e.into())? {
-            return Err(GameServiceError::GameAlreadyRunning);
+        if setup.deadline.is_some_and(|deadline| deadline <= Utc::now().naive_utc()) {
+            return Err(GameServiceError::DeadlineInPast);
         }
 
-        self.insert_test_data().await?;
+        // Ensure every player plays as an existing person card, before any are created
+        let person_count = self.res.evd_cats_and_cards[PERSON_CAT_IDX].cards.len() as i64;
+        if let Some(player) = setup.players.iter().find(|player| !(0..person_count).contains(&player.card_idx)) {
+            return Err(GameServiceError::PersonCardOutOfRange(player.card_idx));
+        }
+
+        // Gather the person card of every player, which no two may share
+        let mut card_idxs = Vec::new();
+        for user_id in &setup.user_ids {
+            let card_idx = self.data_layer.get_user_card_idx(*user_id).await.map_err(|e| e.into())?
+                .ok_or(GameServiceError::UserNotFound(*user_id))?;
+            card_idxs.push(card_idx);
+        }
+        card_idxs.extend(setup.players.iter().map(|player| player.card_idx));
 
-        // For each category, select one card as the target card
+        let mut seen_card_idxs = HashSet::new();
+        if let Some(card_idx) = card_idxs.iter().find(|card_idx| !seen_card_idxs.insert(**card_idx)) {
+            return Err(GameServiceError::DuplicatePersonCard(*card_idx));
+        }
+        let mut seen_emails = HashSet::new();
+        if let Some(player) = setup.players.iter().find(|player| !seen_emails.insert(player.email.as_str())) {
+            return Err(GameServiceError::DuplicateEmail(player.email.clone()));
+        }
+
+        // Choose the murdered player, either the one given or at random
+        let murdered_player = match setup.murdered_card_idx {
+            Some(card_idx) => card_idxs.iter().position(|pl_card_idx| *pl_card_idx == card_idx)
+                .ok_or(GameServiceError::MurderedPlayerNotFound(card_idx))?,
+            None => (0..card_idxs.len()).choose(&mut rng).ok_or(GameServiceError::UsersNotFound)?,
+        };
+        let murdered_card_idx = card_idxs[murdered_player];
+
+        // Validate the new players and hash their passwords, for them to be created with the game
+        let mut players: Vec<_> = setup.user_ids.into_iter().map(GamePlayerEntity::Existing).collect();
+        for player in setup.players {
+            let pwd_hash = self.auth_service.prepare_new_user(&player.email, &player.password, player.card_idx)
+                .await.map_err(|e| e.into())?;
+            players.push(GamePlayerEntity::New { email: player.email, pwd_hash, card_idx: player.card_idx });
+        }
+
+        // For each category, select one card as the target card. 
+        // The murdered player cannot be their own murderer
         let mut target_cards = Vec::new();
         for (cat_idx, cat) in self.res.evd_cats_and_cards.iter().enumerate() {
-            let (card_idx, _) = cat.cards.iter().enumerate().choose(&mut rng).expect("Could not find all category cards.");
-            target_cards.push(CardModel { cat_idx: cat_idx as i64, card_idx: card_idx as i64 });
+            let card_idx = (0..cat.cards.len() as i64)
+                .filter(|card_idx| cat_idx != PERSON_CAT_IDX || *card_idx != murdered_card_idx)
+                .choose(&mut rng)
+                .ok_or(GameServiceError::NoSuspectsAvailable)?;
+            target_cards.push(CardModel { cat_idx: cat_idx as i64, card_idx });
+        }
+
+        let game = NewGameEntity {
+            name: setup.name.unwrap_or_else(|| "Mystery".to_string()),
+            winner_limit: setup.winner_limit,
+            deadline: setup.deadline,
+            partial_feedback: setup.partial_feedback,
+            target_cards,
+            players,
+            murdered_player,
+        };
+        let (game_id, murdered_user_id) = self.data_layer.create_game(&game, &self.res.lvl_stats(1)).await.map_err(|e| e.into())?;
+
+        Ok(GameInitialStateModel { game_id, murdered_user_id, target_cards: game.target_cards, })
+    }
+
+    async fn get_games(&self, user_id: i64) -> Result<Vec<GameModel>> {
+        self.data_layer.get_games(user_id).await.map_err(|e| e.into())
+    }
+
+    async fn join_game(&self, game_id: i64, user_id: i64) -> Result<()> {
+        if !self.data_layer.is_game_active(game_id).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::GameNotRunning);
+        }
+        if self.data_layer.is_pl_in_game(game_id, user_id).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::AlreadyInGame);
+        }
+
+        // Players start every game afresh, at the full stats of the first level
+        self.data_layer.add_player(game_id, user_id, &self.res.lvl_stats(1)).await.map_err(|e| e.into())
+    }
+
+    async fn end_game(&self, game_id: i64) -> Result<ArchivedGameModel> {
+        // Games past their deadline are no longer active, but can still be ended
+        if !self.data_layer.is_game_unarchived(game_id).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::GameNotRunning);
         }
+        self.data_layer.archive_game(game_id).await.map_err(|e| e.into())?;
+
+        self.reveal_game(game_id).await
+    }
+
+    async fn reveal_game(&self, game_id: i64) -> Result<ArchivedGameModel> {
+        self.data_layer.get_archived_game(game_id).await.map_err(|e| e.into())?
+            .ok_or(GameServiceError::GameNotEnded)
+    }
+
+    async fn reset_game(&self, game_id: i64) -> Result<()> {
+        // Ensure the game has ended, so its state is no longer in use
+        self.reveal_game(game_id).await?;
+        self.data_layer.reset_game(game_id, &self.res.lvl_stats(1)).await.map_err(|e| e.into())
+    }
 
-        self.data_layer.setup_game(&target_cards, &self.res.user_base_stats).await.map_err(|e| e.into())?;
-        self.auth_service.print_all_access_tokens().await.map_err(|e| e.into())?;
+    async fn ensure_pl_in_game(&self, game_id: i64, user_id: i64) -> Result<()> {
+        if !self.data_layer.is_game_active(game_id).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::GameNotRunning);
+        }
+        self.ensure_pl_was_in_game(game_id, user_id).await
+    }
 
-        Ok(GameInitialStateModel { target_cards, })
+    async fn ensure_pl_was_in_game(&self, game_id: i64, user_id: i64) -> Result<()> {
+        if !self.data_layer.is_pl_in_game(game_id, user_id).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::NotInGame);
+        }
+        Ok(())
     }
 
-    async fn game_state<'a>(&self, user_id: i64) -> Result<GameStateModel> {
-        let state_model = self.data_layer.game_state(user_id).await.map_err(|e| e.into())?;
-        let completed_riddle_count = self.data_layer.get_completed_riddle_count(user_id).await.map_err(|e| e.into())?;
+    async fn game_state<'a>(&self, game_id: i64, user_id: i64) -> Result<GameStateModel> {
+        if !self.data_layer.is_pl_in_game(game_id, user_id).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::NotInGame);
+        }
+        let state_model = self.data_layer.game_state(game_id, user_id).await.map_err(|e| e.into())?;
+        let solved_riddle_count = self.data_layer.get_solved_riddle_count(game_id, user_id).await.map_err(|e| e.into())?;
+        let notebook = self.get_notebook(game_id, user_id).await?;
+        let achievements = self.achievement_service.get_achievements(user_id).await.map_err(|e| e.into())?;
         state_model.and_then(|mut model| {
-            if completed_riddle_count as usize == self.res.riddles.len() {
+            model.notebook = notebook;
+            model.achievements = achievements;
+            if solved_riddle_count as usize == self.res.riddles.len() {
                 model.pl_completed_all_riddles = true;
             }
+            model.pl_xp_to_next_lvl = self.res.xp_to_next_lvl(model.pl_xp);
+            model.secs_to_deadline = model.deadline
+                .map(|deadline| (deadline - Utc::now().naive_utc()).num_seconds().max(0));
             Some(model)
         }).ok_or(GameServiceError::GameNotRunning)
     }
 
-    async fn guess_target_cards<'a>(&self, user_id: i64, guess: &'a [i64]) -> Result<GuessResult> {
-        let winners = self.game_state(user_id).await?.winner_idxs;
+    async fn guess_target_cards<'a>(&self, game_id: i64, user_id: i64, guess: &'a HashMap<String, i64>) -> Result<GuessResult> {
+        self.ensure_pl_in_game(game_id, user_id).await?;
+
+        // Resolve each guessed card's category by its tag, ensuring the card exists
+        let mut guess_cards = Vec::new();
+        for (tag, card_idx) in guess {
+            let cat_idx = self.res.evd_cat_idx(tag).ok_or_else(|| GameServiceError::UnknownCategory(tag.clone()))?;
+            if !(0..self.res.evd_cats_and_cards[cat_idx].cards.len() as i64).contains(card_idx) {
+                return Err(GameServiceError::GuessOutOfRange);
+            }
+            guess_cards.push(CardModel { cat_idx: cat_idx as i64, card_idx: *card_idx });
+        }
+        if let Some(cat) = self.res.evd_cats_and_cards.iter().find(|cat| !guess.contains_key(&cat.tag)) {
+            return Err(GameServiceError::MissingCategory(cat.tag.clone()));
+        }
+        guess_cards.sort_by_key(|card| card.cat_idx);
+
+        // Read the state directly, so newly unlocked achievements aren't marked as seen
+        let winners = self.data_layer.game_state(game_id, user_id).await.map_err(|e| e.into())?
+            .ok_or(GameServiceError::GameNotRunning)?.winner_idxs;
         if winners.is_some() {
             return Ok(GuessResult::AlreadyWon);
         }
-        let guessed_today = self.data_layer.pl_guessed_today(user_id).await.map_err(|e| e.into())?;
-        if guessed_today {
-            return Ok(GuessResult::AlreadyGuessedToday);
-        }
 
-        let mut target_cards = self.data_layer.get_target_cards().await.map_err(|e| e.into())?;
+        let target_cards = self.data_layer.get_target_cards(game_id).await.map_err(|e| e.into())?;
 
         if target_cards.is_empty() {
             return Err(GameServiceError::GameNotRunning)
         }
 
-        self.data_layer.update_guessed_today(user_id).await.map_err(|e| e.into())?;
-
-        // Sort the target cards by category index (expected order of request guess)
-        target_cards.sort_by(|a, b| a.cat_idx.partial_cmp(&b.cat_idx).unwrap());
-        // If the lengths do not match, the guess is incorrect
-        if guess.len() != target_cards.len() {
-            return Ok(GuessResult::Incorrect);
+        // Use the player's guess for the day, so only one guess a day is ever made
+        if !self.data_layer.update_guessed_today(game_id, user_id).await.map_err(|e| e.into())? {
+            return Ok(GuessResult::AlreadyGuessedToday);
         }
-        // If any target card index does not match the guess card index, 
-        // the guess is incorrect
-        if target_cards.iter().zip(guess).any(|(f, s)| f.card_idx != *s) {
-            return Ok(GuessResult::Incorrect);
+
+        // Count the categories in which the guessed card is the target card.
+        // The guess is only correct if every category matches
+        let correct_cats = guess_cards.iter().filter(|card| target_cards.contains(card)).count();
+        let correct = correct_cats == target_cards.len();
+
+        // Only reveal how many categories were right in games with partial feedback
+        let partial_feedback = self.data_layer.has_partial_feedback(game_id).await.map_err(|e| e.into())?;
+        let correct_cats = (partial_feedback && !correct).then_some(correct_cats as i64);
+        self.data_layer.add_target_guess(game_id, user_id, &guess_cards, correct, correct_cats).await.map_err(|e| e.into())?;
+
+        if !correct {
+            return Ok(GuessResult::Incorrect(correct_cats));
         }
 
         // Otherwise, guess is correct - insert user as new winner 
-        self.data_layer.add_new_winner(user_id).await.map_err(|e| e.into())?;
+        self.data_layer.add_new_winner(game_id, user_id).await.map_err(|e| e.into())?;
+        // The win is already recorded, so failing to unlock achievements must not fail the guess
+        if let Err(e) = self.achievement_service.handle_event(user_id, GameEvent::GameWon { game_id }).await {
+            error!("Failed to unlock achievements for user {} winning game {}: {:?}", user_id, game_id, e);
+        }
 
-        let ans = <[i64;3]>::try_from(guess);
-        Ok(GuessResult::Correct(ans.unwrap()))
+        // End the game once enough players have won
+        if self.data_layer.winner_limit_reached(game_id).await.map_err(|e| e.into())? {
+            self.end_game(game_id).await?;
+        }
+
+        Ok(GuessResult::Correct(guess.clone()))
     }
 
-    async fn update_user_card(&self, user_id: i64, cat_idx: i64, card_idx: i64, guessed: bool) -> Result<()> {
-        if cat_idx as usize >= self.res.evd_cats_and_cards.len() || 
-           card_idx as usize >= self.res.evd_cats_and_cards[cat_idx as usize].cards.len() {
-            return Err(GameServiceError::GuessOutOfRange);
+    async fn get_notebook(&self, game_id: i64, user_id: i64) -> Result<Vec<NotebookCardModel>> {
+        let entities = self.data_layer.get_notebook(game_id, user_id).await.map_err(|e| e.into())?;
+
+        let mut notebook = Vec::new();
+        for entity in entities {
+            notebook.push(NotebookCardModel {
+                cat_idx: entity.cat_idx,
+                card_idx: entity.card_idx,
+                state: CardState::try_from(entity.state)?,
+                note: entity.note,
+                source: match (entity.source_quest_id, entity.source_trade_id) {
+                    (Some(quest_id), _) => Some(CardSourceModel::Quest {
+                        quest_id,
+                        quest_type: entity.source_quest_type.and_then(|typ| QuestKind::try_from(typ).ok()),
+                    }),
+                    (None, Some(trade_id)) => Some(CardSourceModel::Trade { trade_id }),
+                    (None, None) => None,
+                },
+            });
         }
-        self.data_layer.update_user_card(user_id, cat_idx, card_idx, guessed).await.map_err(|e| e.into())?;
-        Ok(())
+        Ok(notebook)
     }
-    async fn confirm_user_card(&self, user_id: i64, cat_idx: i64, card_idx: i64) -> Result<()> {
-        self.data_layer.confirm_user_card(user_id, cat_idx, card_idx).await.map_err(|e| e.into())?;
+
+    async fn update_notebook(&self, game_id: i64, user_id: i64, updates: Vec<NotebookUpdateModel>) -> Result<Vec<NotebookCardModel>> {
+        self.ensure_pl_in_game(game_id, user_id).await?;
+
+        // Validate every update before applying any
+        for update in &updates {
+            let cat = usize::try_from(update.cat_idx).ok().and_then(|cat_idx| self.res.evd_cats_and_cards.get(cat_idx));
+            if !cat.is_some_and(|cat| (0..cat.cards.len() as i64).contains(&update.card_idx)) {
+                return Err(GameServiceError::GuessOutOfRange);
+            }
+            if update.state == Some(CardState::Confirmed) {
+                return Err(GameServiceError::CannotConfirmCard);
+            }
+        }
+        self.data_layer.update_notebook(game_id, user_id, &updates).await.map_err(|e| e.into())?;
+
+        self.get_notebook(game_id, user_id).await
+    }
+
+    async fn confirm_user_card(&self, game_id: i64, user_id: i64, cat_idx: i64, card_idx: i64, source: Option<CardSource>) -> Result<()> {
+        self.data_layer.confirm_user_card(game_id, user_id, cat_idx, card_idx, source).await.map_err(|e| e.into())?;
         Ok(())
     }
+
+    async fn offer_trade(&self, game_id: i64, user_id: i64, offer: TradeOfferModel) -> Result<TradeModel> {
+        self.ensure_pl_in_game(game_id, user_id).await?;
+
+        if offer.to_user_id == user_id {
+            return Err(GameServiceError::CannotTradeWithSelf);
+        }
+        if !self.data_layer.is_pl_in_game(game_id, offer.to_user_id).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::NotInGame);
+        }
+        self.ensure_can_trade(game_id, user_id).await?;
+        self.ensure_tradeable(game_id, user_id, &offer.card).await?;
+
+        let trade_id = self.data_layer.create_trade(game_id, user_id, offer.to_user_id, &offer.card).await.map_err(|e| e.into())?;
+        self.get_trade(game_id, trade_id).await
+    }
+
+    async fn accept_trade(&self, game_id: i64, user_id: i64, trade_id: i64, card: CardModel) -> Result<TradeModel> {
+        self.ensure_pl_in_game(game_id, user_id).await?;
+
+        let trade = self.get_trade(game_id, trade_id).await?;
+        if trade.to_user_id != user_id {
+            return Err(GameServiceError::TradeNotFound);
+        }
+        if trade.status != TradeStatus::Pending {
+            return Err(GameServiceError::TradeNotPending);
+        }
+        self.ensure_tradeable(game_id, user_id, &card).await?;
+
+        // Resolve the trade, count it for both players and confirm their cards all at once
+        match self.data_layer.accept_trade(game_id, &trade, &card, self.settings.daily_trade_limit).await.map_err(|e| e.into())? {
+            TradeAcceptance::Accepted => {},
+            TradeAcceptance::NotPending => return Err(GameServiceError::TradeNotPending),
+            TradeAcceptance::LimitReached => return Err(GameServiceError::DailyTradeLimitReached),
+        }
+
+        self.get_trade(game_id, trade_id).await
+    }
+
+    async fn decline_trade(&self, game_id: i64, user_id: i64, trade_id: i64) -> Result<TradeModel> {
+        self.ensure_pl_in_game(game_id, user_id).await?;
+
+        let trade = self.get_trade(game_id, trade_id).await?;
+        if trade.to_user_id != user_id {
+            return Err(GameServiceError::TradeNotFound);
+        }
+        if !self.data_layer.resolve_trade(trade_id, TradeStatus::Declined, None).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::TradeNotPending);
+        }
+
+        self.get_trade(game_id, trade_id).await
+    }
+
+    async fn get_trades(&self, game_id: i64, user_id: i64) -> Result<Vec<TradeModel>> {
+        if !self.data_layer.is_pl_in_game(game_id, user_id).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::NotInGame);
+        }
+        self.data_layer.get_trades(game_id, user_id).await.map_err(|e| e.into())?
+            .into_iter().map(trade_model).collect()
+    }
+
+    async fn get_leaderboard(&self, game_id: i64, user_id: i64) -> Result<Vec<LeaderboardEntryModel>> {
+        if !self.data_layer.is_pl_in_game(game_id, user_id).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::NotInGame);
+        }
+        let mut leaderboard = self.data_layer.get_leaderboard(game_id).await.map_err(|e| e.into())?;
+
+        // As with the winners in the game state, the win order is hidden until the user has won
+        let has_won = leaderboard.iter().any(|entry| entry.user_id == user_id && entry.win_place.is_some());
+        let has_ended = !self.data_layer.is_game_unarchived(game_id).await.map_err(|e| e.into())?;
+        if !has_won && !has_ended {
+            leaderboard.iter_mut().for_each(|entry| entry.win_place = None);
+        }
+
+        leaderboard.sort_by_key(|entry| (
+            entry.win_place.is_none(), entry.win_place, -entry.confirmed_cards, -entry.quests_completed
+        ));
+        Ok(leaderboard)
+    }
 }
 
 impl DbGameService {
     ///
-    /// Inserts testing data into the game at initial setup
-    /// 
-    async fn insert_test_data(&self) -> Result<()> {
-        let users = [
-            (0, "AlyssaC", "AlyssaHillen"),
-            (1, "AlyssaQ", "AlyssaSchmid"),
-            (2, "Andrea", "AndreaBuckalew"),
-            (3, "Brian", "BrianHall"),
-            (4, "Chris",  "ChrisSchmid"),
-            (5, "Coraline", "CoralineSchmid"),
-            (6, "Kim", "KimSchmid"),
-            (7, "Kunane", "KunaneHillen"),
-            (8, "Maria", "MariaMcGowan"),
-            (9, "Miranda", "MirandaHall"),
-            (10, "MJ", "MJSchmid"),
-            (11, "Paisley", "PaisleySchmid"),
-            (12, "Zach", "ZachBuckalew"),
-        ];
-
-        for (card_idx, email, pwd) in users.iter() {
-            self.auth_service.create_new_user(
-                email.to_string(), 
-                pwd.to_string(), 
-                *card_idx
-            ).await.map_err(|e| e.into())?;
-        }
+    /// Retrieves the trade with the given `trade_id` in the game
+    /// 
+    async fn get_trade(&self, game_id: i64, trade_id: i64) -> Result<TradeModel> {
+        let trade = self.data_layer.get_trade(game_id, trade_id).await.map_err(|e| e.into())?
+            .ok_or(GameServiceError::TradeNotFound)?;
+        trade_model(trade)
+    }
 
+    ///
+    /// Ensures the user has not reached their daily trade limit in the game
+    /// 
+    async fn ensure_can_trade(&self, game_id: i64, user_id: i64) -> Result<()> {
+        let trades_today = self.data_layer.get_pl_trades_today(game_id, user_id).await.map_err(|e| e.into())?;
+        if trades_today >= self.settings.daily_trade_limit {
+            return Err(GameServiceError::DailyTradeLimitReached);
+        }
         Ok(())
     }
 
-    
+    ///
+    /// Ensures the user can trade away the given `card` - it must be one of their
+    /// confirmed cards, and never one of the game's target cards
+    /// 
+    async fn ensure_tradeable(&self, game_id: i64, user_id: i64, card: &CardModel) -> Result<()> {
+        if !self.data_layer.is_card_confirmed(game_id, user_id, card).await.map_err(|e| e.into())? {
+            return Err(GameServiceError::CardNotConfirmed);
+        }
+        let target_cards = self.data_layer.get_target_cards(game_id).await.map_err(|e| e.into())?;
+        if target_cards.contains(card) {
+            return Err(GameServiceError::TargetCardNotTradeable);
+        }
+        Ok(())
+    }
 }
 
+///
+/// Converts a stored trade into its model
+/// 
+fn trade_model(trade: TradeEntity) -> Result<TradeModel> {
+    Ok(TradeModel {
+        id: trade.id,
+        from_user_id: trade.from_user_id,
+        to_user_id: trade.to_user_id,
+        offered_card: CardModel { cat_idx: trade.offered_cat_idx, card_idx: trade.offered_card_idx },
+        returned_card: trade.returned_cat_idx.zip(trade.returned_card_idx)
+            .map(|(cat_idx, card_idx)| CardModel { cat_idx, card_idx }),
+        status: TradeStatus::try_from(trade.status)?,
+        created_on: trade.created_on,
+        resolved_on: trade.resolved_on,
+    })
+}
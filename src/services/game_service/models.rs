@@ -1,18 +1,167 @@
-use crate::resources::game_resources::BaseStats;
+use std::collections::HashMap;
+
+use crate::{resources::game_resources::BaseStats, services::{achievement_service::models::AchievementModel, quest_service::models::QuestKind}};
+use chrono::NaiveDateTime;
 use derive_more::Constructor;
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug, PartialEq, Serialize)]
+use super::error::GameServiceError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct CardModel {
     pub cat_idx: i64,
     pub card_idx: i64
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct UserCardModel {
+///
+/// The state of a card in a player's notebook. Players mark cards as suspected or 
+/// ruled out, while cards are only confirmed when revealed by the game. Stored in 
+/// the database by discriminant
+/// 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CardState {
+    #[serde(rename="suspected")]
+    Suspected = 0,
+    #[serde(rename="ruled-out")]
+    RuledOut = 1,
+    #[serde(rename="confirmed")]
+    Confirmed = 2,
+}
+
+impl TryFrom<i64> for CardState {
+    type Error = GameServiceError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        [CardState::Suspected, CardState::RuledOut, CardState::Confirmed].into_iter()
+            .find(|state| *state as i64 == value)
+            .ok_or(GameServiceError::UnknownCardState(value))
+    }
+}
+
+///
+/// What revealed a confirmed card to a player
+/// 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardSource {
+    Quest(i64),
+    Trade(i64),
+}
+
+///
+/// What revealed a confirmed card, either a quest (battles are revealed by 
+/// monster quests) or a trade with another player
+/// 
+#[derive(Debug, Serialize)]
+#[serde(tag="kind")]
+pub enum CardSourceModel {
+    #[serde(rename="quest")]
+    Quest { quest_id: i64, quest_type: Option<QuestKind> },
+    #[serde(rename="trade")]
+    Trade { trade_id: i64 },
+}
+
+///
+/// The status of a card trade. Stored in the database by discriminant
+/// 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TradeStatus {
+    #[serde(rename="pending")]
+    Pending = 0,
+    #[serde(rename="accepted")]
+    Accepted = 1,
+    #[serde(rename="declined")]
+    Declined = 2,
+}
+
+impl TryFrom<i64> for TradeStatus {
+    type Error = GameServiceError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        [TradeStatus::Pending, TradeStatus::Accepted, TradeStatus::Declined].into_iter()
+            .find(|status| *status as i64 == value)
+            .ok_or(GameServiceError::UnknownTradeStatus(value))
+    }
+}
+
+///
+/// An offer of one of the player's confirmed cards to the player with `to_user_id`,
+/// who returns one of their own confirmed cards if they accept
+/// 
+#[derive(Debug, Deserialize)]
+pub struct TradeOfferModel {
+    pub to_user_id: i64,
+    pub card: CardModel,
+}
+
+///
+/// A card trade between two players. The returned card is only known once accepted
+/// 
+#[derive(Debug, Serialize)]
+pub struct TradeModel {
+    pub id: i64,
+    pub from_user_id: i64,
+    pub to_user_id: i64,
+    pub offered_card: CardModel,
+    pub returned_card: Option<CardModel>,
+    pub status: TradeStatus,
+    pub created_on: NaiveDateTime,
+    pub resolved_on: Option<NaiveDateTime>,
+}
+
+///
+/// A card the player has made a note of, or had confirmed
+/// 
+#[derive(Debug, Serialize)]
+pub struct NotebookCardModel {
+    pub cat_idx: i64,
+    pub card_idx: i64,
+    pub state: CardState,
+    pub note: Option<String>,
+    pub source: Option<CardSourceModel>,
+}
+
+///
+/// An update to a card in the player's notebook. Without a `state` or `note`,
+/// the card is removed from the notebook. Confirmed cards keep their state
+/// 
+#[derive(Debug, Deserialize)]
+pub struct NotebookUpdateModel {
     pub cat_idx: i64,
     pub card_idx: i64,
-    pub confirmed: bool
+    pub state: Option<CardState>,
+    pub note: Option<String>,
+}
+
+///
+/// A player to create when setting up a game, who plays as 
+/// the person card at `card_idx`
+/// 
+#[derive(Deserialize)]
+pub struct PlayerSetupModel {
+    pub email: String,
+    pub password: String,
+    pub card_idx: i64,
+}
+
+///
+/// Configuration of a new game. `players` are created before setup, and join the game 
+/// alongside the existing users in `user_ids`. The murdered player is chosen by their 
+/// person card at `murdered_card_idx`, or randomly if not given. The game ends
+/// automatically once `winner_limit` players have won, or at the `deadline` (UTC), if given.
+/// With `partial_feedback`, incorrect guesses report how many categories were right
+/// 
+#[derive(Deserialize, Default)]
+pub struct GameSetupModel {
+    pub name: Option<String>,
+    pub winner_limit: Option<i64>,
+    pub deadline: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub partial_feedback: bool,
+    #[serde(default)]
+    pub players: Vec<PlayerSetupModel>,
+    #[serde(default)]
+    pub user_ids: Vec<i64>,
+    pub murdered_card_idx: Option<i64>,
 }
 
 ///
@@ -20,17 +169,96 @@ pub struct UserCardModel {
 /// 
 #[derive(Serialize)]
 pub struct GameInitialStateModel {
+    pub game_id: i64,
+    pub murdered_user_id: i64,
     pub target_cards: Vec<CardModel>,
 }
 
+///
+/// Summary of a game, and whether the requesting user has joined it
+/// 
+#[derive(Serialize)]
+pub struct GameModel {
+    pub id: i64,
+    pub name: String,
+    pub created_on: NaiveDateTime,
+    pub deadline: Option<NaiveDateTime>,
+    pub archived_on: Option<NaiveDateTime>,
+    pub joined: bool,
+}
+
+///
+/// A finished game, with its solution and winners in the order they won
+/// 
+#[derive(Serialize)]
+pub struct ArchivedGameModel {
+    pub id: i64,
+    pub name: String,
+    pub created_on: NaiveDateTime,
+    pub archived_on: NaiveDateTime,
+    pub murdered_user_id: i64,
+    pub target_cards: Vec<CardModel>,
+    pub winner_idxs: Vec<i64>,
+}
+
+///
+/// A player's final place in an ended game. Winners are placed in the order
+/// they won, followed by the rest by their number of confirmed cards
+/// 
+#[derive(Debug, Serialize)]
+pub struct StandingModel {
+    pub place: i64,
+    pub user_id: i64,
+    pub card_idx: i64,
+    pub won: bool,
+    pub confirmed_cards: i64,
+}
+
+///
+/// A player's past guess of the target cards, with one card per category.
+/// `correct_cats` is only known in games with partial feedback
+/// 
+#[derive(Debug, Serialize)]
+pub struct TargetGuessModel {
+    pub cards: Vec<CardModel>,
+    pub correct: bool,
+    pub correct_cats: Option<i64>,
+    pub created_on: NaiveDateTime,
+}
+
+///
+/// A player's progress in a game. Only counts are given, so no player's cards are revealed.
+/// `win_place` is only known to players who have won, or once the game has ended
+/// 
+#[derive(Debug, Serialize)]
+pub struct LeaderboardEntryModel {
+    pub user_id: i64,
+    pub card_idx: i64,
+    pub confirmed_cards: i64,
+    pub quests_completed: i64,
+    pub monsters_defeated: i64,
+    pub riddles_solved: i64,
+    pub win_place: Option<i64>,
+}
+
 #[derive(Serialize)]
 pub struct GameStateModel {
+    pub game_id: i64,
     pub user_id: i64,
     pub murdered_user_id: i64,
     pub user_stats: Stats,
-    pub user_cards: Vec<UserCardModel>,
+    pub pl_lvl: i64,
+    pub pl_xp: i64,
+    pub pl_xp_to_next_lvl: Option<i64>,
+    pub notebook: Vec<NotebookCardModel>,
+    pub achievements: Vec<AchievementModel>,
     pub target_cards: Option<Vec<CardModel>>,
     pub winner_idxs: Option<Vec<i64>>,
+    pub standings: Option<Vec<StandingModel>>,
+    pub deadline: Option<NaiveDateTime>,
+    pub secs_to_deadline: Option<i64>,
+    pub partial_feedback: bool,
+    pub pl_guesses: Vec<TargetGuessModel>,
     pub pl_exhausted: bool,
     pub pl_completed_daily_riddle: bool,
     pub pl_completed_all_riddles: bool,
@@ -48,10 +276,12 @@ pub struct Stats {
 
 #[derive(Debug, Serialize)]
 pub enum GuessResult {
+    /// Holds the correct guess, keyed by category tag
     #[serde(rename="correct")]
-    Correct([i64;3]),
+    Correct(HashMap<String, i64>),
+    /// Holds the number of correct categories, in games with partial feedback
     #[serde(rename="incorrect")]
-    Incorrect,
+    Incorrect(Option<i64>),
     #[serde(rename="already-won")]
     AlreadyWon,
     #[serde(rename="already-guessed-today")]
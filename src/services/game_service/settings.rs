@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize)]
+pub struct GameSettings {
+    ///
+    /// The number of card trades each player can make per day
+    /// 
+    pub daily_trade_limit: i64,
+}
@@ -5,66 +5,102 @@ use derive_more::Constructor;
 use rand::{seq::IteratorRandom, rngs::StdRng, SeedableRng};
 use sqlx::SqlitePool;
 
-use crate::{data_layer_error::Result, resources::game_resources::{BaseStats, EvidenceCardCategories}, services::game_service::models::{CardModel, Stats}};
+use crate::{data_layer_error::Result, resources::game_resources::{BaseStats, EvidenceCardCategories}, services::game_service::models::{CardModel, CardState, Stats}};
 
-use super::entities::{QuestMonsterEntity, QuestStateEntity};
+use super::models::{QuestKind, RiddleGuessModel};
+use super::entities::{QuestMonsterEntity, QuestRiddleEntity, QuestStateEntity, RiddleHintOutcome};
 
 #[async_trait]
 pub trait QuestDataLayer : Send + Sync {
-    async fn pl_has_won_game(&self, user_id: i64) -> Result<bool>;
+    async fn pl_has_won_game(&self, game_id: i64, user_id: i64) -> Result<bool>;
     ///
-    /// Retrieves the current-day quest (if one exists) of the user specified by `user_id`
-    /// Quests are active if they are both not marked as `completed`, and are from the current day.
+    /// Retrieves the current-day quest (if one exists) of the user specified by `user_id`, in the game
+    /// with the given `game_id`. Quests are active if they are both not marked as `completed`, and are from the current day.
     /// 
-    async fn get_active_user_quest(&self, user_id: i64) -> Result<Option<QuestStateEntity>>;
+    async fn get_active_user_quest(&self, game_id: i64, user_id: i64) -> Result<Option<QuestStateEntity>>;
     ///
-    /// Retrieves the player's current level. Players level up when they complete monster quests.
+    /// Retrieves the player's current level in the game. Players level up when they complete monster quests.
     /// 
-    async fn get_pl_lvl(&self, user_id: i64) -> Result<i64>;
+    async fn get_pl_lvl(&self, game_id: i64, user_id: i64) -> Result<i64>;
     ///
-    /// Retrieves whether the player has completed a riddle quest today
+    /// Retrieves whether the player has completed a riddle quest today, in the game with the given `game_id`
     /// 
-    async fn pl_answered_riddle(&self, user_id: i64) -> Result<bool>;
+    async fn pl_answered_riddle(&self, game_id: i64, user_id: i64) -> Result<bool>;
     ///
     /// Creates a battle quest for the specified user, by `user_id`.
     /// If there is already an existing quest for the day and it's completed,
     /// this increments the quest level and activates it. If it's not completed
     /// returns None
     /// 
-    async fn create_new_user_quest(&self, user_id: i64, quest_type: i64) -> Result<Option<QuestStateEntity>>;
+    async fn create_new_user_quest(&self, user_id: i64, game_id: i64, quest_type: i64) -> Result<Option<QuestStateEntity>>;
     ///
-    /// Completes the quest of the user with the given `user_id`
+    /// Marks the quest with the given `quest_id` as completed, if it is still active,
+    /// noting if the user `succeeded` in it
     /// 
-    async fn complete_quest(&self, user_id: i64) -> Result<()>;
+    async fn complete_quest(&self, quest_id: i64, succeeded: bool) -> Result<()>;
+    ///
+    /// Adds `xp` to the player's total XP in the game, returning the new total
+    /// 
+    async fn add_pl_xp(&self, game_id: i64, user_id: i64, xp: i64) -> Result<i64>;
+    ///
+    /// Sets the player's `lvl` in the game with the given `game_id`, and restores their health and armor to the given `stats`
+    /// 
+    async fn set_pl_lvl(&self, game_id: i64, user_id: i64, lvl: i64, stats: BaseStats) -> Result<()>;
+    ///
+    /// Records a monster battle won by the player today in the game with the given `game_id`, 
+    /// returning the number of battles won today
+    /// 
+    async fn record_pl_battle_win(&self, game_id: i64, user_id: i64) -> Result<i64>;
+    ///
+    /// Marks the player as having completed their riddle quest today, in the game with the given `game_id`
+    /// 
+    async fn set_pl_answered_riddle(&self, game_id: i64, user_id: i64) -> Result<()>;
     ///
     /// Exhausts the player, preventing them from performing any more battle quests that day
+    /// in the game with the given `game_id`
     /// 
-    async fn exhaust_pl(&self, user_id: i64) -> Result<()>;
+    async fn exhaust_pl(&self, game_id: i64, user_id: i64) -> Result<()>;
     ///
-    /// Returns whether the player is exhausted today
+    /// Returns whether the player is exhausted today, in the game with the given `game_id`
     /// 
-    async fn pl_is_exhausted(&self, user_id: i64) -> Result<bool>;
+    async fn pl_is_exhausted(&self, game_id: i64, user_id: i64) -> Result<bool>;
     ///
     /// Creates a new monster with the specified stats, and assigns to the given quest
     /// 
     async fn create_quest_monster(&self, quest_id: i64, monster_idx: i64, stats: BaseStats) -> Result<()>;
     ///
-    /// Retrieves the indices of all riddles the user has answered
+    /// Retrieves the indices of all riddles the user has answered correctly in the game with the given `game_id`
     /// 
-    async fn get_user_answered_riddle(&self, user_id: i64) -> Result<Vec<i64>>;
+    async fn get_user_answered_riddle(&self, game_id: i64, user_id: i64) -> Result<Vec<i64>>;
     ///
     /// Creates a new riddle with the specified index, and assigns to the given quest
     /// 
     async fn create_quest_riddle(&self, quest_id: i64, riddle_idx: i64) -> Result<()>;
     ///
-    /// Retrieves the index of the current user's quest's riddle
+    /// Retrieves the riddle state of the current user's quest in the game with the given `game_id`, 
+    /// if it is a riddle quest
+    /// 
+    async fn get_quest_riddle(&self, game_id: i64, user_id: i64) -> Result<Option<QuestRiddleEntity>>;
+    ///
+    /// Uses a hint on the riddle of the given quest, while fewer than `max_hints` have been used. Spends one of the
+    /// player's `daily_hint_allowance` in the game, or otherwise one of their items with the given `hint_item_idx`.
+    /// Returns the new number of hints used on the riddle
+    /// 
+    async fn use_riddle_hint(&self, game_id: i64, user_id: i64, quest_id: i64, max_hints: i64, daily_hint_allowance: i64, hint_item_idx: Option<i64>) -> Result<RiddleHintOutcome>;
+    ///
+    /// Records a guess made by the user on the riddle of the given quest, and increments the number
+    /// of attempts made on it. Returns the new number of attempts, or None if `max_attempts` were already made
     /// 
-    async fn get_quest_riddle_idx(&self, user_id: i64) -> Result<Option<i64>>;
+    async fn record_riddle_guess<'a>(&self, user_id: i64, quest_id: i64, guess: &'a str, correct: bool, max_attempts: i64) -> Result<Option<i64>>;
+    ///
+    /// Retrieves every riddle guess made by all users, most recent first
+    /// 
+    async fn get_riddle_guesses(&self) -> Result<Vec<RiddleGuessModel>>;
     /// 
     /// Retrieves a new, random evidence card, if any exist that has yet to be confirmed
-    /// in the user's collection
+    /// in the user's collection for the game with the given `game_id`
     /// 
-    async fn get_rand_unconfirmed_card<'a>(&self, user_id: i64, all_cards: &'a [EvidenceCardCategories]) -> Result<Option<CardModel>>;
+    async fn get_rand_unconfirmed_card<'a>(&self, game_id: i64, user_id: i64, all_cards: &'a [EvidenceCardCategories]) -> Result<Option<CardModel>>;
     ///
     /// Deletes the quest with the given id
     /// 
@@ -78,19 +114,19 @@ pub struct DbQuestDataLayer {
 
 #[async_trait]
 impl QuestDataLayer for DbQuestDataLayer {
-    async fn pl_has_won_game(&self, user_id: i64) -> Result<bool> {
+    async fn pl_has_won_game(&self, game_id: i64, user_id: i64) -> Result<bool> {
         Ok(
-            sqlx::query!("SELECT * FROM game_winners WHERE user_id = ?", user_id)
+            sqlx::query!("SELECT * FROM game_winners WHERE game_id = ? AND user_id = ?", game_id, user_id)
                 .fetch_optional(&self.db).await?.is_some()
         )
     }
-    async fn get_active_user_quest(&self, user_id: i64) -> Result<Option<QuestStateEntity>> {
-        // Get the most recent, incomplete quest for the user (if one exists)
+    async fn get_active_user_quest(&self, game_id: i64, user_id: i64) -> Result<Option<QuestStateEntity>> {
+        // Get the most recent, incomplete quest for the user in the game (if one exists)
         let quest = sqlx::query!("
-            SELECT id, quest_type, completed FROM quests 
-            WHERE user_id = ? AND completed = FALSE
+            SELECT id, game_id, quest_type, completed FROM quests 
+            WHERE game_id = ? AND user_id = ? AND completed = FALSE
             ORDER BY created_on DESC
-            ", user_id
+            ", game_id, user_id
         ).fetch_optional(&self.db).await?;
 
         if let Some(quest) = quest {
@@ -107,23 +143,24 @@ impl QuestDataLayer for DbQuestDataLayer {
                     stats: Stats::new(row.health, row.power, row.armor, row.missing_next_turn)
                 }));
 
-            // Get the riddle idx for the quest if it's a riddle quest
-            let riddle_idx = sqlx::query!("SELECT riddle_idx FROM quest_riddles WHERE quest_id = ?", quest.id)
-                .fetch_optional(&self.db).await?
-                .and_then(|row| Some(row.riddle_idx));
+            // Get the riddle state for the quest if it's a riddle quest
+            let riddle_state = sqlx::query_as!(QuestRiddleEntity, 
+                "SELECT quest_id, riddle_idx, hints_used, attempts FROM quest_riddles WHERE quest_id = ?", 
+                quest.id
+            ).fetch_optional(&self.db).await?;
 
             return Ok(Some(QuestStateEntity { 
-                id: quest.id, quest_type: quest.quest_type,
-                monster_state, riddle_idx,
+                id: quest.id, game_id: quest.game_id, quest_type: quest.quest_type,
+                monster_state, riddle_state,
                 completed: quest.completed
             }));
         }
 
         Ok(None)
     }
-    async fn create_new_user_quest(&self, user_id: i64, quest_type: i64) -> Result<Option<QuestStateEntity>> {
+    async fn create_new_user_quest(&self, user_id: i64, game_id: i64, quest_type: i64) -> Result<Option<QuestStateEntity>> {
         // Determine if the user has a daily quest already
-        let daily_quest = self.get_active_user_quest(user_id).await?;
+        let daily_quest = self.get_active_user_quest(game_id, user_id).await?;
 
         // If there is a daily quest and it's completed, create new quest and set lvl to incremented value
         if let Some(daily_quest) = daily_quest {
@@ -134,22 +171,22 @@ impl QuestDataLayer for DbQuestDataLayer {
         } 
 
         // Create the new quest
-        sqlx::query!("INSERT INTO quests (user_id, quest_type) VALUES (?, ?)", user_id, quest_type)
+        sqlx::query!("INSERT INTO quests (game_id, user_id, quest_type) VALUES (?, ?, ?)", game_id, user_id, quest_type)
             .execute(&self.db).await?;
 
-        Ok(Some(self.get_active_user_quest(user_id).await?.unwrap()))
+        Ok(Some(self.get_active_user_quest(game_id, user_id).await?.unwrap()))
     }
 
-    async fn get_pl_lvl(&self, user_id: i64) -> Result<i64> {
+    async fn get_pl_lvl(&self, game_id: i64, user_id: i64) -> Result<i64> {
         Ok(
-            sqlx::query!("SELECT lvl FROM users WHERE id = ?", user_id)
+            sqlx::query!("SELECT lvl FROM user_states WHERE game_id = ? AND user_id = ?", game_id, user_id)
                 .fetch_one(&self.db).await?.lvl
         )
     }
 
-    async fn pl_answered_riddle(&self, user_id: i64) -> Result<bool> {
+    async fn pl_answered_riddle(&self, game_id: i64, user_id: i64) -> Result<bool> {
         Ok(
-            sqlx::query!("SELECT riddle_quest_completed FROM users WHERE id = ?", user_id)
+            sqlx::query!("SELECT riddle_quest_completed FROM user_states WHERE game_id = ? AND user_id = ?", game_id, user_id)
                 .fetch_one(&self.db).await?.riddle_quest_completed
         )
     }
@@ -169,12 +206,13 @@ impl QuestDataLayer for DbQuestDataLayer {
         Ok(())
     }
 
-    async fn get_user_answered_riddle(&self, user_id: i64) -> Result<Vec<i64>> {
-        // Get all user completed quests that are riddle quests
+    async fn get_user_answered_riddle(&self, game_id: i64, user_id: i64) -> Result<Vec<i64>> {
+        // Get the riddles of all the user's riddle quests in the game that were answered correctly
+        let riddle_type = QuestKind::Riddle as i64;
         let riddle_idxs: Vec<i64> = sqlx::query!("
             SELECT qr.riddle_idx FROM quests q JOIN quest_riddles qr ON q.id = qr.quest_id 
-            WHERE q.user_id = ? AND q.quest_type = 1 AND q.completed = TRUE
-            ", user_id
+            WHERE q.game_id = ? AND q.user_id = ? AND q.quest_type = ? AND q.succeeded = TRUE
+            ", game_id, user_id, riddle_type
         )
             .fetch_all(&self.db).await?
             .iter().map(|q| q.riddle_idx).collect();
@@ -191,78 +229,141 @@ impl QuestDataLayer for DbQuestDataLayer {
         Ok(())
     }
 
-    async fn get_quest_riddle_idx(&self, user_id: i64) -> Result<Option<i64>> {
-        let quest = self.get_active_user_quest(user_id).await?;
+    async fn get_quest_riddle(&self, game_id: i64, user_id: i64) -> Result<Option<QuestRiddleEntity>> {
+        let quest = self.get_active_user_quest(game_id, user_id).await?;
         if let Some(quest) = quest {
-            if quest.quest_type == 1 {
-                let riddle_idx = sqlx::query!("SELECT riddle_idx FROM quest_riddles WHERE quest_id = ?", quest.id)
-                    .fetch_one(&self.db).await?.riddle_idx;
-
-                return Ok(Some(riddle_idx));
+            if quest.quest_type == QuestKind::Riddle as i64 {
+                return Ok(quest.riddle_state);
             }
         }
         Ok(None)
     }
 
-    async fn complete_quest(&self, user_id: i64) -> Result<()> {
-        // Get the quest's id
-        let quest = sqlx::query!(
-            "SELECT id, quest_type FROM quests WHERE user_id = ? AND completed = FALSE", 
-            user_id
-        )  
-            .fetch_optional(&self.db).await?;
+    async fn use_riddle_hint(&self, game_id: i64, user_id: i64, quest_id: i64, max_hints: i64, daily_hint_allowance: i64, hint_item_idx: Option<i64>) -> Result<RiddleHintOutcome> {
+        let mut tx = self.db.begin().await?;
+
+        // Take a hint first, so no more than the maximum are ever used
+        let hints_used = sqlx::query!(
+            "UPDATE quest_riddles SET hints_used = hints_used + 1 WHERE quest_id = ? AND hints_used < ? RETURNING hints_used", 
+            quest_id, max_hints
+        ).fetch_optional(&mut *tx).await?;
+        let Some(hints_used) = hints_used else { return Ok(RiddleHintOutcome::LimitReached) };
+
+        // Use a daily hint if any remain, otherwise consume a single, unequipped hint item
+        let used_daily_hint = sqlx::query!("
+            UPDATE user_states SET hints_used_today = hints_used_today + 1 
+            WHERE game_id = ? AND user_id = ? AND hints_used_today < ?
+            ", game_id, user_id, daily_hint_allowance
+        ).execute(&mut *tx).await?.rows_affected() > 0;
+
+        if !used_daily_hint {
+            let Some(item_idx) = hint_item_idx else { return Ok(RiddleHintOutcome::NoHintsAvailable) };
+            let consumed_item = sqlx::query!("
+                DELETE FROM user_items WHERE id = (
+                    SELECT ui.id FROM user_items ui 
+                    WHERE ui.user_id = ? AND ui.item_idx = ? 
+                    AND NOT EXISTS (SELECT * FROM user_equipped_items uei WHERE uei.item_id = ui.id)
+                    LIMIT 1
+                )
+                ", user_id, item_idx
+            ).execute(&mut *tx).await?.rows_affected() > 0;
+            if !consumed_item {
+                return Ok(RiddleHintOutcome::NoHintsAvailable);
+            }
+        }
+
+        tx.commit().await?;
+        Ok(RiddleHintOutcome::Revealed(hints_used.hints_used))
+    }
+
+    async fn record_riddle_guess<'a>(&self, user_id: i64, quest_id: i64, guess: &'a str, correct: bool, max_attempts: i64) -> Result<Option<i64>> {
+        let mut tx = self.db.begin().await?;
 
-        if quest.is_none() { return Ok(()); }
-        let quest = quest.unwrap();
+        // Take an attempt first, so no more than the maximum are ever made
+        let attempts = sqlx::query!(
+            "UPDATE quest_riddles SET attempts = attempts + 1 WHERE quest_id = ? AND attempts < ? RETURNING attempts", 
+            quest_id, max_attempts
+        ).fetch_optional(&mut *tx).await?;
+        let Some(attempts) = attempts else { return Ok(None) };
 
+        sqlx::query!("
+            INSERT INTO riddle_guesses (quest_id, user_id, guess, correct) VALUES (?, ?, ?, ?)
+            ", quest_id, user_id, guess, correct
+        ).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+        Ok(Some(attempts.attempts))
+    }
+
+    async fn get_riddle_guesses(&self) -> Result<Vec<RiddleGuessModel>> {
+        Ok(
+            sqlx::query_as!(RiddleGuessModel, "
+                SELECT rg.user_id, rg.quest_id, qr.riddle_idx, rg.guess, rg.correct, rg.created_on 
+                FROM riddle_guesses rg JOIN quest_riddles qr ON rg.quest_id = qr.quest_id
+                ORDER BY rg.id DESC
+            ").fetch_all(&self.db).await?
+        )
+    }
+
+    async fn complete_quest(&self, quest_id: i64, succeeded: bool) -> Result<()> {
         // Update the quest as completed
-        sqlx::query!("UPDATE quests SET completed = TRUE WHERE id = ?", quest.id)
+        sqlx::query!("UPDATE quests SET completed = TRUE, succeeded = ? WHERE id = ? AND completed = FALSE", succeeded, quest_id)
             .execute(&self.db).await?;
+        Ok(())
+    }
 
-        if quest.quest_type == 0 {
-            // If it was a monster battle, check if the user's lvl is currently 2
-            // if so, set the player to exhausted
-            let lvl = sqlx::query!("SELECT lvl FROM users WHERE id = ?", user_id)
-                .fetch_one(&self.db).await?.lvl;
-            if lvl == 2 {
-                self.exhaust_pl(user_id).await?;
-            } else {
-                sqlx::query!("
-                    UPDATE stats SET health = 10 
-                    WHERE EXISTS (
-                        SELECT * FROM user_states
-                        WHERE stats_id = stats.id AND user_id = ?
-                    )
-                ", user_id).execute(&self.db).await?;
-                // Set the user's lvl to 2
-                sqlx::query!("UPDATE users SET lvl = 2 WHERE id = ?", user_id)
-                    .execute(&self.db).await?;
-            }
-        } else {
-            // If it was a riddle quest, update the user as completed a riddle today
-            sqlx::query!("UPDATE users SET riddle_quest_completed = TRUE WHERE id = ?", user_id)
-                .execute(&self.db).await?;
-        }
+    async fn add_pl_xp(&self, game_id: i64, user_id: i64, xp: i64) -> Result<i64> {
+        Ok(
+            sqlx::query!("UPDATE user_states SET xp = xp + ? WHERE game_id = ? AND user_id = ? RETURNING xp", xp, game_id, user_id)
+                .fetch_one(&self.db).await?.xp
+        )
+    }
 
+    async fn set_pl_lvl(&self, game_id: i64, user_id: i64, lvl: i64, stats: BaseStats) -> Result<()> {
+        sqlx::query!("
+            UPDATE stats SET health = ?, armor = ? 
+            WHERE EXISTS (
+                SELECT * FROM user_states
+                WHERE stats_id = stats.id AND game_id = ? AND user_id = ?
+            )
+        ", stats.health, stats.armor, game_id, user_id).execute(&self.db).await?;
+
+        sqlx::query!("UPDATE user_states SET lvl = ? WHERE game_id = ? AND user_id = ?", lvl, game_id, user_id)
+            .execute(&self.db).await?;
+        Ok(())
+    }
 
+    async fn record_pl_battle_win(&self, game_id: i64, user_id: i64) -> Result<i64> {
+        Ok(
+            sqlx::query!(
+                "UPDATE user_states SET battles_won_today = battles_won_today + 1 WHERE game_id = ? AND user_id = ? RETURNING battles_won_today", 
+                game_id, user_id
+            ).fetch_one(&self.db).await?.battles_won_today
+        )
+    }
+
+    async fn set_pl_answered_riddle(&self, game_id: i64, user_id: i64) -> Result<()> {
+        sqlx::query!("UPDATE user_states SET riddle_quest_completed = TRUE WHERE game_id = ? AND user_id = ?", game_id, user_id)
+            .execute(&self.db).await?;
         Ok(())
     }
 
-    async fn exhaust_pl(&self, user_id: i64) -> Result<()> {
-        sqlx::query!("UPDATE users SET exhausted = TRUE WHERE id = ?", user_id)
+    async fn exhaust_pl(&self, game_id: i64, user_id: i64) -> Result<()> {
+        sqlx::query!("UPDATE user_states SET exhausted = TRUE WHERE game_id = ? AND user_id = ?", game_id, user_id)
             .execute(&self.db).await?;
         Ok(())
     }
 
-    async fn pl_is_exhausted(&self, user_id: i64) -> Result<bool> {
+    async fn pl_is_exhausted(&self, game_id: i64, user_id: i64) -> Result<bool> {
         Ok(
-            sqlx::query!("SELECT exhausted FROM users WHERE id = ?", user_id)
+            sqlx::query!("SELECT exhausted FROM user_states WHERE game_id = ? AND user_id = ?", game_id, user_id)
                 .fetch_one(&self.db).await?.exhausted
         )
     }
 
-    async fn get_rand_unconfirmed_card<'a>(&self, user_id: i64, cards: &'a [EvidenceCardCategories]) -> Result<Option<CardModel>> {
+    async fn get_rand_unconfirmed_card<'a>(&self, game_id: i64, user_id: i64, cards: &'a [EvidenceCardCategories]) -> Result<Option<CardModel>> {
         let mut rng = StdRng::from_entropy();
+        let confirmed = CardState::Confirmed as i64;
 
         // Create an iterator of all permutations of cat idx to card idx
         let card_cat_pairs = cards.iter().enumerate()
@@ -271,8 +372,8 @@ impl QuestDataLayer for DbQuestDataLayer {
         // Get all confirmed user cards, convert into 2-ples of cat and card idxs
         // and collect into a HashSet
         let mut conf_cat_card_idxs = sqlx::query!(
-            "SELECT cat_idx, card_idx FROM user_cards WHERE user_id = ? AND confirmed = TRUE", 
-            user_id
+            "SELECT cat_idx, card_idx FROM user_cards WHERE game_id = ? AND user_id = ? AND state = ?", 
+            game_id, user_id, confirmed
         )
             .fetch_all(&self.db).await?
             .iter().map(|card| (card.cat_idx, card.card_idx))
@@ -280,7 +381,8 @@ impl QuestDataLayer for DbQuestDataLayer {
         
         // Map game target cards into confirmed cards - they should
         // never be chosen!
-        let game_target_cards = sqlx::query!("SELECT * FROM game_target_cards").fetch_all(&self.db).await?;
+        let game_target_cards = sqlx::query!("SELECT * FROM game_target_cards WHERE game_id = ?", game_id)
+            .fetch_all(&self.db).await?;
         for idxs in game_target_cards.iter() {
             conf_cat_card_idxs.insert((idxs.cat_idx, idxs.card_idx));
         }
@@ -5,9 +5,10 @@ use crate::services::game_service::models::Stats;
 #[derive(Serialize)]
 pub struct QuestStateEntity {
     pub id: i64,
+    pub game_id: i64,
     pub quest_type: i64,
     pub monster_state: Option<QuestMonsterEntity>,
-    pub riddle_idx: Option<i64>,
+    pub riddle_state: Option<QuestRiddleEntity>,
     pub completed: bool
 }
 
@@ -15,4 +16,22 @@ pub struct QuestStateEntity {
 pub struct QuestMonsterEntity {
     pub monster_idx: i64,
     pub stats: Stats,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize)]
+pub struct QuestRiddleEntity {
+    pub quest_id: i64,
+    pub riddle_idx: i64,
+    pub hints_used: i64,
+    pub attempts: i64,
+}
+
+///
+/// The outcome of using a hint on a riddle. A hint is only revealed while letters remain to
+/// reveal, and the player has a daily hint or hint item to spend on it
+/// 
+pub enum RiddleHintOutcome {
+    Revealed(i64),
+    LimitReached,
+    NoHintsAvailable,
+}
@@ -23,7 +23,17 @@ pub enum QuestServiceError {
     #[error("Only one riddle quest can be completed a day")]
     PlayerAlreadyCompletedRiddle,
     #[error("Player is exhausted, and cannot start a battle quest today.")]
-    PlayerIsExhausted
+    PlayerIsExhausted,
+    #[error("No attempts remain for this riddle")]
+    NoRiddleAttemptsLeft,
+    #[error("No hints remaining today, and no hint item in inventory")]
+    NoHintsAvailable,
+    #[error("No more letters can be revealed for this riddle")]
+    HintLimitReached,
+    #[error("There are no monsters to battle")]
+    NoMonstersAvailable,
+    #[error("Unknown quest kind `{0}`")]
+    UnknownQuestKind(String),
 }
 
 impl Into<QuestServiceError> for DataLayerError {
@@ -0,0 +1,55 @@
+pub mod monster_quest;
+pub mod riddle_quest;
+
+use std::{collections::HashMap, sync::Arc};
+
+use axum::async_trait;
+
+use super::{entities::QuestStateEntity, error::{QuestServiceError, Result}, models::{QuestKind, QuestStateModel}};
+
+///
+/// Logic specific to a single kind of quest, which the `QuestService`
+/// defers to when generating, completing and failing quests of that kind
+/// 
+#[async_trait]
+pub trait QuestKindHandler : Send + Sync {
+    ///
+    /// Creates the kind-specific state of the newly created `quest`.
+    /// Throws Error if the user cannot start this kind of quest
+    /// 
+    async fn generate(&self, user_id: i64, quest: &QuestStateEntity) -> Result<QuestStateModel>;
+    ///
+    /// Applies the effects of the user with the given `user_id` completing the `quest`
+    /// 
+    async fn complete(&self, user_id: i64, quest: &QuestStateEntity) -> Result<()>;
+    ///
+    /// Applies the effects of the user with the given `user_id` failing the `quest`
+    /// 
+    async fn fail(&self, user_id: i64, quest: &QuestStateEntity) -> Result<()>;
+}
+
+///
+/// Collection of the `QuestKindHandler`s for every kind of quest that can be played
+/// 
+#[derive(Default)]
+pub struct QuestRegistry {
+    handlers: HashMap<QuestKind, Arc<dyn QuestKindHandler>>,
+}
+
+impl QuestRegistry {
+    ///
+    /// Registers the `handler` as the logic for quests of the given `kind`
+    /// 
+    pub fn with_kind(mut self, kind: QuestKind, handler: Arc<dyn QuestKindHandler>) -> Self {
+        self.handlers.insert(kind, handler);
+        self
+    }
+
+    ///
+    /// Retrieves the handler for quests of the given `kind`.
+    /// Throws Error if no handler has been registered for the kind
+    /// 
+    pub fn get(&self, kind: QuestKind) -> Result<&Arc<dyn QuestKindHandler>> {
+        self.handlers.get(&kind).ok_or(QuestServiceError::UnknownQuestKind(kind.to_string()))
+    }
+}
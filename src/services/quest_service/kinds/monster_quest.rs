@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+use derive_more::Constructor;
+use rand::{seq::IteratorRandom, thread_rng};
+
+use crate::{resources::game_resources::Resources, services::game_service::models::Stats};
+
+use super::{super::{entities::QuestStateEntity, data_layer::QuestDataLayer, error::{QuestServiceError, Result}, models::{QuestKind, QuestMonsterModel, QuestStateModel}, settings::QuestSettings}, QuestKindHandler};
+
+///
+/// Quest in which the player battles a monster of their level, gaining XP on victory
+/// 
+#[derive(Constructor)]
+pub struct MonsterQuest {
+    data_layer: Arc<dyn QuestDataLayer>,
+    res: Arc<Resources>,
+    settings: QuestSettings,
+}
+
+#[async_trait]
+impl QuestKindHandler for MonsterQuest {
+    async fn generate(&self, user_id: i64, quest: &QuestStateEntity) -> Result<QuestStateModel> {
+        if self.data_layer.pl_is_exhausted(quest.game_id, user_id).await.map_err(|e| e.into())? {
+            return Err(QuestServiceError::PlayerIsExhausted);
+        }
+        let pl_lvl = self.data_layer.get_pl_lvl(quest.game_id, user_id).await.map_err(|e| e.into())?;
+
+        // Choose a new monster to fight the player, from the closest level with monsters
+        let monster_lvl = self.res.monster_lvl_for(pl_lvl).ok_or(QuestServiceError::NoMonstersAvailable)?;
+        let (monster_idx, monster) = self.res.monsters
+            .iter().enumerate().filter(|(_, monster)| monster.level == monster_lvl)
+            .choose(&mut thread_rng())
+            .ok_or(QuestServiceError::NoMonstersAvailable)?;
+
+        let monster_idx = monster_idx as i64;
+
+        self.data_layer.create_quest_monster(quest.id, monster_idx, monster.stats).await.map_err(|e| e.into())?;
+
+        Ok(QuestStateModel {
+            quest_type: QuestKind::Monster,
+            monster_state: Some(QuestMonsterModel {
+                res_idx: monster_idx,
+                stats: Stats::from_base_stats(monster.stats)
+            }),
+            riddle_state: None,
+        })
+    }
+
+    async fn complete(&self, user_id: i64, quest: &QuestStateEntity) -> Result<()> {
+        let monster_state = quest.monster_state.as_ref().ok_or(QuestServiceError::UserNotOnQuest)?;
+        let monster_lvl = self.res.monsters[monster_state.monster_idx as usize].level;
+
+        // Award the player XP for the monster, and heal them to the full stats of their new level
+        let pl_xp = self.data_layer.add_pl_xp(quest.game_id, user_id, self.res.monster_xp(monster_lvl)).await.map_err(|e| e.into())?;
+        let pl_lvl = self.res.lvl_for_xp(pl_xp);
+        self.data_layer.set_pl_lvl(quest.game_id, user_id, pl_lvl, self.res.lvl_stats(pl_lvl)).await.map_err(|e| e.into())?;
+
+        // Exhaust the player once they have won all their battles for the day
+        let battles_won = self.data_layer.record_pl_battle_win(quest.game_id, user_id).await.map_err(|e| e.into())?;
+        if battles_won >= self.settings.daily_battle_limit {
+            self.data_layer.exhaust_pl(quest.game_id, user_id).await.map_err(|e| e.into())?;
+        }
+        Ok(())
+    }
+
+    async fn fail(&self, user_id: i64, quest: &QuestStateEntity) -> Result<()> {
+        self.data_layer.exhaust_pl(quest.game_id, user_id).await.map_err(|e| e.into())
+    }
+}
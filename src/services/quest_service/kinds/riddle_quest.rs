@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+use derive_more::Constructor;
+use rand::{seq::IteratorRandom, thread_rng};
+
+use crate::resources::game_resources::Resources;
+
+use super::{super::{entities::QuestStateEntity, data_layer::QuestDataLayer, error::{QuestServiceError, Result}, models::{QuestKind, QuestStateModel}, riddle_model, settings::QuestSettings}, QuestKindHandler};
+
+///
+/// Quest in which the player answers a riddle they have not yet solved.
+/// Only one riddle quest may be completed a day
+/// 
+#[derive(Constructor)]
+pub struct RiddleQuest {
+    data_layer: Arc<dyn QuestDataLayer>,
+    res: Arc<Resources>,
+    settings: QuestSettings,
+}
+
+#[async_trait]
+impl QuestKindHandler for RiddleQuest {
+    async fn generate(&self, user_id: i64, quest: &QuestStateEntity) -> Result<QuestStateModel> {
+        // Ensure the user hasn't already completed a riddle today
+        if self.data_layer.pl_answered_riddle(quest.game_id, user_id).await.map_err(|e| e.into())? {
+            return Err(QuestServiceError::PlayerAlreadyCompletedRiddle)
+        }
+
+        let ans_riddle_idxs = self.data_layer.get_user_answered_riddle(quest.game_id, user_id).await.map_err(|e| e.into())?;
+
+        // Choose a new riddle to give the player,
+        // that the player has not yet solved in this game
+        let (idx, riddle) = self.res.riddles
+            .iter().enumerate()
+            .filter(|(idx, _)| !ans_riddle_idxs.contains(&(*idx as i64)))
+            .choose(&mut thread_rng())
+            .ok_or(QuestServiceError::AllRiddlesCompleted)?;
+
+        self.data_layer.create_quest_riddle(quest.id, idx as i64).await.map_err(|e| e.into())?;
+
+        Ok(QuestStateModel {
+            quest_type: QuestKind::Riddle,
+            monster_state: None,
+            riddle_state: Some(riddle_model(riddle, 0, self.settings.max_riddle_attempts)),
+        })
+    }
+
+    async fn complete(&self, user_id: i64, quest: &QuestStateEntity) -> Result<()> {
+        self.data_layer.set_pl_answered_riddle(quest.game_id, user_id).await.map_err(|e| e.into())
+    }
+
+    async fn fail(&self, user_id: i64, quest: &QuestStateEntity) -> Result<()> {
+        // A failed riddle still uses up the player's riddle for the day
+        self.data_layer.set_pl_answered_riddle(quest.game_id, user_id).await.map_err(|e| e.into())
+    }
+}
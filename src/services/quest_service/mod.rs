@@ -2,49 +2,65 @@ pub mod data_layer;
 pub mod error;
 pub mod models;
 pub mod entities;
+pub mod settings;
+pub mod kinds;
 
 use std::sync::Arc;
 
 use axum::async_trait;
 use derive_more::Constructor;
-use rand::{seq::{IteratorRandom, SliceRandom}, thread_rng};
+use log::error;
+use rand::{seq::SliceRandom, thread_rng, Rng};
 
 use self::models::{
-    QuestReward, RiddleStatus, QuestStateModel, QuestRiddleModel, QuestMonsterModel, QuestConsequences,
+    QuestKind, QuestReward, RiddleStatus, QuestStateModel, QuestRiddleModel, QuestMonsterModel, QuestConsequences, RiddleGuessModel,
 };
 
-use crate::resources::game_resources::Resources;
+use crate::resources::game_resources::{Resources, Riddle};
 
-use self::{error::{Result, QuestServiceError}, data_layer::QuestDataLayer};
+use self::{error::{Result, QuestServiceError}, data_layer::QuestDataLayer, entities::{QuestStateEntity, RiddleHintOutcome}, kinds::QuestRegistry, settings::QuestSettings};
 
-use super::game_service::{models::Stats, GameService};
+use super::achievement_service::{models::GameEvent, AchievementService};
+use super::game_service::{models::CardSource, GameService};
 
 #[async_trait]
 pub trait QuestService: Send + Sync {
     ///
-    /// Generates a new quest of the specified `quest_type` for the user with the given `user_id`.
-    /// Throws Error if the user already has an active quest
+    /// Generates a new quest of the specified `quest_kind` for the user with the given `user_id`,
+    /// in the game with the given `game_id`. Throws Error if the user already has an active quest,
+    /// or is not playing in the game
     /// 
-    async fn generate_quest(&self, user_id: i64, quest_type: i64) -> Result<QuestStateModel>;
+    async fn generate_quest(&self, user_id: i64, game_id: i64, quest_kind: QuestKind) -> Result<QuestStateModel>;
     ///
-    /// Returns the users current quest, if they are on one. Returns error if the user is not on a quest
+    /// Returns the users current quest in the game with the given `game_id`, if they are on one. 
+    /// Returns error if the user is not on a quest in the game
     /// 
-    async fn get_quest(&self, user_id: i64) -> Result<QuestStateModel>;
+    async fn get_quest(&self, user_id: i64, game_id: i64) -> Result<QuestStateModel>;
     ///
-    /// Performs a guess on a riddle quest, which the user given the `user_id` has active.
-    /// Throws Error if the user is not on a riddle quest
+    /// Performs a guess on a riddle quest, which the user given the `user_id` has active in the game
+    /// with the given `game_id`. Throws Error if the user is not on a riddle quest
     /// 
-    async fn guess_riddle(&self, user_id: i64, answer: String) -> Result<RiddleStatus>;
+    async fn guess_riddle(&self, user_id: i64, game_id: i64, answer: String) -> Result<RiddleStatus>;
     ///
-    /// Completes the quest the user with the given `user_id` is currently on.
+    /// Reveals the next letter of the answer to the riddle quest the user with the given `user_id`
+    /// has active in the game with the given `game_id`. Consumes one of the user's daily hints in the game, 
+    /// or a hint item if none remain. Throws Error if the user is not on a riddle quest, or has no hints available
+    /// 
+    async fn use_riddle_hint(&self, user_id: i64, game_id: i64) -> Result<QuestRiddleModel>;
+    ///
+    /// Retrieves the log of all guesses made on riddle quests
+    /// 
+    async fn get_riddle_guesses(&self) -> Result<Vec<RiddleGuessModel>>;
+    ///
+    /// Completes the quest the user with the given `user_id` is currently on in the game with the given `game_id`.
     /// Returns a `QuestReward`, with new confirmed card for user (if not all cards are confirmed already)
     /// 
-    async fn complete_quest(&self, user_id: i64) -> Result<QuestReward>;
+    async fn complete_quest(&self, user_id: i64, game_id: i64) -> Result<QuestReward>;
     ///
-    /// Completes te quest the user with the given `user_id` is currently on.
+    /// Completes te quest the user with the given `user_id` is currently on in the game with the given `game_id`.
     /// Returns a `QuestConsequences`, which include ailments the user now has.  
     /// 
-    async fn fail_quest(&self, user_id: i64) -> Result<QuestConsequences>;
+    async fn fail_quest(&self, user_id: i64, game_id: i64) -> Result<QuestConsequences>;
 }
 
 #[derive(Constructor)]
@@ -52,113 +68,162 @@ pub struct CoreQuestService {
     data_layer: Arc<dyn QuestDataLayer>,
     res: Arc<Resources>,
     game_service: Arc<dyn GameService>,
+    achievement_service: Arc<dyn AchievementService>,
+    settings: QuestSettings,
+    registry: QuestRegistry,
 }
 
 #[async_trait]
 impl QuestService for CoreQuestService {
-    async fn generate_quest(&self, user_id: i64, quest_type: i64) -> Result<QuestStateModel> {
-        let quest = self.data_layer.create_new_user_quest(user_id, quest_type).await.map_err(|e| e.into())?;
-        let pl_lvl = self.data_layer.get_pl_lvl(user_id).await.map_err(|e| e.into())?;
-        
-        if let Some(quest) = quest {
-            let (mut monster_state, mut riddle_state) = (None, None);
-            match quest_type {
-                0 => {
-                    if self.data_layer.pl_is_exhausted(user_id).await.map_err(|e| e.into())? {
-                        // If the player is exhausted, delete the quest that was just created
-                        // and return the Error
-                        self.data_layer.delete_quest(quest.id).await.map_err(|e| e.into())?;
-                        return Err(QuestServiceError::PlayerIsExhausted);
-                    }
-                    monster_state = Some(self.generate_monster_quest(quest.id, pl_lvl).await?);
-                }
-                1 => {
-                    match self.generate_riddle_quest(user_id, quest.id).await {
-                        Ok(model) => riddle_state = Some(model),
-                        Err(QuestServiceError::AllRiddlesCompleted) => {
-                            // If all riddles were completed, delete the quest that was just created
-                            // and return the Error
-                            self.data_layer.delete_quest(quest.id).await.map_err(|e| e.into())?;
-                            return Err(QuestServiceError::AllRiddlesCompleted);
-                        },
-                        Err(QuestServiceError::PlayerAlreadyCompletedRiddle) => {
-                            // If player already completed riddle today, 
-                            // delete the quest that was just created and return the Error
-                            self.data_layer.delete_quest(quest.id).await.map_err(|e| e.into())?;
-                            return Err(QuestServiceError::PlayerAlreadyCompletedRiddle);
-                        },
-                        Err(e) => return Err(e)
-                    }
-                }
-                _ => { }
+    async fn generate_quest(&self, user_id: i64, game_id: i64, quest_kind: QuestKind) -> Result<QuestStateModel> {
+        self.game_service.ensure_pl_in_game(game_id, user_id).await.map_err(|e| e.into())?;
+
+        let handler = self.registry.get(quest_kind)?;
+        let quest = self.data_layer.create_new_user_quest(user_id, game_id, quest_kind as i64).await.map_err(|e| e.into())?
+            .ok_or(QuestServiceError::QuestAlreadyActive)?;
+
+        match handler.generate(user_id, &quest).await {
+            Ok(model) => Ok(model),
+            Err(e) => {
+                // If the quest could not be generated, delete the quest that was 
+                // just created and return the Error
+                self.data_layer.delete_quest(quest.id).await.map_err(|e| e.into())?;
+                Err(e)
             }
-
-            return Ok(QuestStateModel {
-                quest_type: quest.quest_type,
-                monster_state, riddle_state
-            });
-        } 
-
-        Err(QuestServiceError::QuestAlreadyActive)
+        }
     }
 
-    async fn get_quest(&self, user_id: i64) -> Result<QuestStateModel> {
-        let quest = self.data_layer.get_active_user_quest(user_id).await.map_err(|e| e.into())?;
+    async fn get_quest(&self, user_id: i64, game_id: i64) -> Result<QuestStateModel> {
+        let quest = self.data_layer.get_active_user_quest(game_id, user_id).await.map_err(|e| e.into())?;
         match quest {
             None => return Err(QuestServiceError::UserNotOnQuest),
             Some(quest) => {
                 let monster_state = quest.monster_state.and_then(
                     |ms| Some(QuestMonsterModel { stats: ms.stats, res_idx: ms.monster_idx })
                 );
-                let riddle_state = quest.riddle_idx.and_then(
-                    |idx| {
-                        let mut rng = thread_rng();
-                        let ans_scramb = self.res.riddles[idx as usize].answer.clone();
-                        let mut ans_scramb = ans_scramb.chars().collect::<Vec<char>>();
-                        ans_scramb.shuffle(&mut rng);
-                        let ans_scramb = ans_scramb.into_iter().collect();
-                        println!("{ans_scramb}");
-
-                        Some(QuestRiddleModel { 
-                            text: self.res.riddles[idx as usize].text.clone(), 
-                            ans_scramb
-                        })
-                    }
+                let riddle_state = quest.riddle_state.map(
+                    |rs| riddle_model(
+                        &self.res.riddles[rs.riddle_idx as usize], 
+                        rs.hints_used, self.settings.max_riddle_attempts - rs.attempts
+                    )
                 );
+                let quest_type = QuestKind::try_from(quest.quest_type)?;
 
-                return Ok(QuestStateModel { quest_type: quest.quest_type, monster_state, riddle_state });
+                return Ok(QuestStateModel { quest_type, monster_state, riddle_state });
             }
         }
     }
 
-    async fn guess_riddle(&self, user_id: i64, answer: String) -> Result<RiddleStatus> {
-        // Lowercase answer for string-matching
-        let answer = answer.to_lowercase();
+    async fn guess_riddle(&self, user_id: i64, game_id: i64, guess: String) -> Result<RiddleStatus> {
+        // Normalize answer for string-matching
+        let answer = normalize_answer(&guess);
 
-        // Get the user's riddle quest index. Throw error if one isn't found
+        // Get the user's riddle quest state. Throw error if one isn't found
         // (ie. the user is not on a riddle quest)
-        let riddle_idx = self.data_layer.get_quest_riddle_idx(user_id).await.map_err(|e| e.into())?
+        let riddle_state = self.data_layer.get_quest_riddle(game_id, user_id).await.map_err(|e| e.into())?
             .ok_or(QuestServiceError::UserNotOnRiddleQuest)?;
 
-        let riddle = &self.res.riddles[riddle_idx as usize];
+        let riddle = &self.res.riddles[riddle_state.riddle_idx as usize];
+
+        // Find the answer in the collection of answers for the riddle the user is closest to,
+        // along with the number of typos allowed for that answer, and the distance reported as close
+        let (distance, allowance, close_distance) = riddle.answer.iter()
+            .map(|alt| normalize_answer(alt))
+            .map(|alt| (edit_distance(&alt, &answer), self.typo_allowance(&alt), self.close_distance(&alt)))
+            .min_by_key(|(distance, allowance, _)| distance.saturating_sub(*allowance))
+            .unwrap_or((usize::MAX, 0, 0));
+
+        let correct = distance <= allowance;
+        let attempts = self.data_layer.record_riddle_guess(user_id, riddle_state.quest_id, &guess, correct, self.settings.max_riddle_attempts)
+            .await.map_err(|e| e.into())?
+            .ok_or(QuestServiceError::NoRiddleAttemptsLeft)?;
 
         // If the user provides any answer in the collection of answers for the riddle,
-        // quest is successfully completed
-        if riddle.answer.to_lowercase() == answer {
-            return Ok(RiddleStatus::Correct(self.complete_quest(user_id).await.map_err(|e| e.into())?));
+        // quest is successfully completed. Each hint used lowers the chance of a card reward
+        if correct {
+            let card_chance = 1.0 - self.settings.hint_card_chance_penalty * riddle_state.hints_used as f64;
+            return Ok(RiddleStatus::Correct(self.reward_quest(user_id, game_id, card_chance).await?));
+        }
+
+        // If the user has run out of attempts, the quest is failed
+        let attempts_left = self.settings.max_riddle_attempts - attempts;
+        if attempts_left <= 0 {
+            return Ok(RiddleStatus::Failed(self.fail_quest(user_id, game_id).await?));
+        }
+
+        if distance <= close_distance {
+            return Ok(RiddleStatus::Close { attempts_left });
         }
-        return Ok(RiddleStatus::Incorrect);
+        Ok(RiddleStatus::Incorrect { attempts_left })
     }  
 
+    async fn use_riddle_hint(&self, user_id: i64, game_id: i64) -> Result<QuestRiddleModel> {
+        let riddle_state = self.data_layer.get_quest_riddle(game_id, user_id).await.map_err(|e| e.into())?
+            .ok_or(QuestServiceError::UserNotOnRiddleQuest)?;
+
+        // Ensure there is still a letter left to reveal - the full answer is never given away.
+        // Articles are ignored when matching answers, so they are never counted as letters
+        let riddle = &self.res.riddles[riddle_state.riddle_idx as usize];
+        let letter_count = normalize_answer(riddle.primary_answer()).chars().count() as i64;
+
+        // Use a daily hint if any remain, otherwise a hint item
+        let hint_item_idx = self.res.items.iter().position(|item| item.tag == self.settings.hint_item_tag).map(|idx| idx as i64);
+        let hints_used = match self.data_layer.use_riddle_hint(
+            game_id, user_id, riddle_state.quest_id, letter_count - 1, self.settings.daily_hint_allowance, hint_item_idx
+        ).await.map_err(|e| e.into())? {
+            RiddleHintOutcome::Revealed(hints_used) => hints_used,
+            RiddleHintOutcome::LimitReached => return Err(QuestServiceError::HintLimitReached),
+            RiddleHintOutcome::NoHintsAvailable => return Err(QuestServiceError::NoHintsAvailable),
+        };
+
+        Ok(riddle_model(riddle, hints_used, self.settings.max_riddle_attempts - riddle_state.attempts))
+    }
+
+    async fn get_riddle_guesses(&self) -> Result<Vec<RiddleGuessModel>> {
+        self.data_layer.get_riddle_guesses().await.map_err(|e| e.into())
+    }
+
     ///
-    /// Completes the quest the user with the given `user_id` is currently on.
+    /// Completes the quest the user with the given `user_id` is currently on in the game with the given `game_id`.
     /// Returns a `QuestReward`, with new confirmed card for user (if not all cards are confirmed already)
     /// 
-    async fn complete_quest(&self, user_id: i64) -> Result<QuestReward> {
-        // Complete the quest
-        self.data_layer.complete_quest(user_id).await.map_err(|e| e.into())?;
+    async fn complete_quest(&self, user_id: i64, game_id: i64) -> Result<QuestReward> {
+        self.reward_quest(user_id, game_id, 1.0).await
+    }
+
+    async fn fail_quest(&self, user_id: i64, game_id: i64) -> Result<QuestConsequences> {
+        let (quest_kind, quest) = self.active_quest(user_id, game_id).await?;
+
+        // Complete the quest, and apply the failure effects of its kind
+        self.data_layer.complete_quest(quest.id, false).await.map_err(|e| e.into())?;
+        self.registry.get(quest_kind)?.fail(user_id, &quest).await?;
+
+        Ok(QuestConsequences { sab_idxs: vec![] })
+    }
+}
+
+impl CoreQuestService {
+    ///
+    /// Completes the quest the user with the given `user_id` is currently on in the game with the given `game_id`,
+    /// rewarding a new confirmed card with the given `card_chance` (from `0.0` to `1.0`)
+    /// 
+    async fn reward_quest(&self, user_id: i64, game_id: i64, card_chance: f64) -> Result<QuestReward> {
+        let (quest_kind, quest) = self.active_quest(user_id, game_id).await?;
+
+        // Complete the quest, and apply the completion effects of its kind
+        self.data_layer.complete_quest(quest.id, true).await.map_err(|e| e.into())?;
+        self.registry.get(quest_kind)?.complete(user_id, &quest).await?;
+
+        // Solving riddles counts towards achievements. Battles are handled by the battle service.
+        // The quest is already completed, so failing to unlock achievements must not lose its reward
+        if quest_kind == QuestKind::Riddle {
+            if let Err(e) = self.achievement_service.handle_event(user_id, GameEvent::RiddleSolved).await {
+                error!("Failed to unlock achievements for user {} solving a riddle: {:?}", user_id, e);
+            }
+        }
 
-        if self.data_layer.pl_has_won_game(user_id).await.map_err(|e| e.into())? {
+        if self.data_layer.pl_has_won_game(quest.game_id, user_id).await.map_err(|e| e.into())? 
+            || !thread_rng().gen_bool(card_chance.clamp(0.0, 1.0)) {
             return Ok(
                 QuestReward {
                     item_idxs: vec![],
@@ -168,79 +233,150 @@ impl QuestService for CoreQuestService {
         }
 
         // Get a new confirmed card
-        let new_card = self.data_layer.get_rand_unconfirmed_card(user_id, &self.res.evd_cats_and_cards).await.map_err(|e| e.into())?;
+        let new_card = self.data_layer.get_rand_unconfirmed_card(quest.game_id, user_id, &self.res.evd_cats_and_cards)
+            .await.map_err(|e| e.into())?;
 
-        // Confirm it with the game service
+        // Confirm it with the game service, in the game the quest was played in
         if let Some(card) = &new_card {
-            self.game_service.confirm_user_card(user_id, card.cat_idx, card.card_idx).await
+            self.game_service.confirm_user_card(quest.game_id, user_id, card.cat_idx, card.card_idx, Some(CardSource::Quest(quest.id))).await
                 .map_err(|e| e.into())?;
         }
 
         // Return the successful quest reward
-        return Ok(
+        Ok(
             QuestReward {
                 item_idxs: vec![],
                 card: new_card
             },
-        );
+        )
     }
 
-    async fn fail_quest(&self, user_id: i64) -> Result<QuestConsequences> {
-        // Complete the quest
-        self.data_layer.complete_quest(user_id).await.map_err(|e| e.into())?;
-        self.data_layer.exhaust_pl(user_id).await.map_err(|e| e.into())?;
-        Ok(QuestConsequences { sab_idxs: vec![] })
+    ///
+    /// Returns the number of typos tolerated when guessing the given normalized answer.
+    /// Short answers are held to an exact match, so a single typo can't land on another word
+    /// 
+    fn typo_allowance(&self, answer: &str) -> usize {
+        self.settings.riddle_typo_tolerance.min(answer.chars().count() / 5)
     }
-}
 
-impl CoreQuestService {
-    async fn generate_monster_quest(&self, quest_id: i64, quest_level: i64) -> Result<QuestMonsterModel> {
-        // Choose a new monster to fight the player
-        let (monster_idx, monster) = self.res.monsters
-            .iter().enumerate().filter(|(_, monster)| monster.level == quest_level)
-            .choose(&mut thread_rng()).unwrap();
+    ///
+    /// Returns the greatest distance from the given normalized answer a guess is reported as close at.
+    /// Scaled down for short answers, so being close doesn't give away their length
+    /// 
+    fn close_distance(&self, answer: &str) -> usize {
+        self.settings.riddle_close_distance.min(answer.chars().count() / 3)
+    }
 
-        let monster_idx = monster_idx as i64;
+    ///
+    /// Retrieves the quest the user with the given `user_id` is currently on in the game with the given `game_id`, 
+    /// and its kind. Throws Error if the user is not on a quest in the game
+    /// 
+    async fn active_quest(&self, user_id: i64, game_id: i64) -> Result<(QuestKind, QuestStateEntity)> {
+        let quest = self.data_layer.get_active_user_quest(game_id, user_id).await.map_err(|e| e.into())?
+            .ok_or(QuestServiceError::UserNotOnQuest)?;
+        Ok((QuestKind::try_from(quest.quest_type)?, quest))
+    }
+}
 
-        self.data_layer.create_quest_monster(quest_id, monster_idx, monster.stats).await.map_err(|e| e.into())?;
+///
+/// Builds the model of the given `riddle`, scrambling its answer 
+/// and revealing the first `hints_used` letters of it
+/// 
+fn riddle_model(riddle: &Riddle, hints_used: i64, attempts_left: i64) -> QuestRiddleModel {
+    let mut ans_scramb = riddle.primary_answer().chars().collect::<Vec<char>>();
+    ans_scramb.shuffle(&mut thread_rng());
+
+    QuestRiddleModel {
+        text: riddle.text.clone(),
+        ans_scramb: ans_scramb.into_iter().collect(),
+        hint: if hints_used > 0 { Some(reveal_letters(riddle.primary_answer(), hints_used as usize)) } else { None },
+        hints_used,
+        attempts_left,
+    }
+}
+
+const ARTICLES: [&str; 3] = ["a", "an", "the"];
+
+///
+/// Normalizes a riddle answer for matching - lowercasing it, and removing
+/// articles, punctuation and whitespace (ie. "A glow-stick!" becomes "glowstick")
+/// 
+fn normalize_answer(answer: &str) -> String {
+    answer.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !ARTICLES.contains(word))
+        .collect()
+}
 
-        Ok(QuestMonsterModel {
-            res_idx: monster_idx,
-            stats: Stats::from_base_stats(monster.stats)
-        })
+///
+/// Returns the Levenshtein distance between `a` and `b` - the number of single
+/// character insertions, deletions and substitutions to turn one into the other
+/// 
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut prev_row = (0..=b.len()).collect::<Vec<usize>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let sub_cost = if a_char == *b_char { 0 } else { 1 };
+            row[j + 1] = (prev_row[j] + sub_cost).min(prev_row[j + 1] + 1).min(row[j] + 1);
+        }
+        prev_row = row;
     }
 
-    pub async fn generate_riddle_quest(&self, user_id: i64, quest_id: i64) -> Result<QuestRiddleModel> {
-        // Ensure the user hasn't already completed a riddle today
-        if self.data_layer.pl_answered_riddle(user_id).await.map_err(|e| e.into())? {
-            return Err(QuestServiceError::PlayerAlreadyCompletedRiddle)
+    prev_row[b.len()]
+}
+
+///
+/// Reveals the first `count` letters of the `answer`, hiding every other letter
+/// with an underscore. Whitespace, punctuation and articles are always shown.
+/// 
+fn reveal_letters(answer: &str, count: usize) -> String {
+    let mut revealed = 0;
+    answer.split_inclusive(|c: char| !c.is_alphanumeric()).map(|part| {
+        let word = part.trim_end_matches(|c: char| !c.is_alphanumeric());
+        if ARTICLES.contains(&word.to_lowercase().as_str()) {
+            return part.to_string();
         }
+        part.chars().map(|c| {
+            if !c.is_alphanumeric() {
+                c
+            } else if revealed < count {
+                revealed += 1;
+                c
+            } else {
+                '_'
+            }
+        }).collect()
+    }).collect()
+}
 
-        let ans_riddle_idxs = self.data_layer.get_user_answered_riddle(user_id).await.map_err(|e| e.into())?;
-
-        // Choose a new riddle to give the player,
-        // that the player has not seen
-        let idx_and_riddle = self.res.riddles
-            .iter().enumerate()
-            .filter(|(idx, _)| !ans_riddle_idxs.contains(&(*idx as i64)))
-            .choose(&mut thread_rng());
-
-        return if let Some((idx, riddle)) = idx_and_riddle {
-            self.data_layer.create_quest_riddle(quest_id, idx as i64).await.map_err(|e| e.into())?;
-
-            Ok({
-                let mut rng = thread_rng();
-                let ans_scramb = riddle.answer.clone();
-                let mut ans_scramb = ans_scramb.chars().collect::<Vec<char>>();
-                ans_scramb.shuffle(&mut rng);
-
-                QuestRiddleModel { 
-                    text: riddle.text.clone(), 
-                    ans_scramb: ans_scramb.into_iter().collect()
-                }
-            })
-        } else {
-            Err(QuestServiceError::AllRiddlesCompleted)
-        };
-    } 
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reveal_letters() {
+        assert_eq!(reveal_letters("an echo", 0), "an ____");
+        assert_eq!(reveal_letters("an echo", 3), "an ech_");
+        assert_eq!(reveal_letters("a glow stick", 1), "a g___ _____");
+        assert_eq!(reveal_letters("The letter M", 6), "The letter _");
+        assert_eq!(reveal_letters("a glow stick", 20), "a glow stick");
+    }
+
+    #[test]
+    fn test_normalize_answer() {
+        assert_eq!(normalize_answer("An Echo"), "echo");
+        assert_eq!(normalize_answer("a glow-stick!"), "glowstick");
+        assert_eq!(normalize_answer("  The   letter M "), "letterm");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("glowstick", "glowstick"), 0);
+        assert_eq!(edit_distance("glowstik", "glowstick"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "echo"), 4);
+    }
+}
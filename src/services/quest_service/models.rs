@@ -1,11 +1,62 @@
+use std::{fmt::Display, str::FromStr};
 
 use crate::services::game_service::models::{Stats, CardModel};
 
+use chrono::NaiveDateTime;
 use serde::{self, Serialize};
 
+use super::error::QuestServiceError;
+
+///
+/// The kinds of quest a player can embark on. Stored in the
+/// database by discriminant, and serialized by name
+/// 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum QuestKind {
+    #[serde(rename="monster")]
+    Monster = 0,
+    #[serde(rename="riddle")]
+    Riddle = 1,
+}
+
+impl QuestKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuestKind::Monster => "monster",
+            QuestKind::Riddle => "riddle",
+        }
+    }
+}
+
+impl Display for QuestKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for QuestKind {
+    type Err = QuestServiceError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        [QuestKind::Monster, QuestKind::Riddle].into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or(QuestServiceError::UnknownQuestKind(name.to_string()))
+    }
+}
+
+impl TryFrom<i64> for QuestKind {
+    type Error = QuestServiceError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        [QuestKind::Monster, QuestKind::Riddle].into_iter()
+            .find(|kind| *kind as i64 == value)
+            .ok_or(QuestServiceError::UnknownQuestKind(value.to_string()))
+    }
+}
+
 #[derive(Serialize)]
 pub struct QuestStateModel {
-    pub quest_type: i64,
+    pub quest_type: QuestKind,
     pub monster_state: Option<QuestMonsterModel>,
     pub riddle_state: Option<QuestRiddleModel>,
 }
@@ -20,7 +71,27 @@ pub struct QuestMonsterModel {
 #[derive(Serialize)]
 pub struct QuestRiddleModel {
     pub text: String,
-    pub ans_scramb: String
+    pub ans_scramb: String,
+    ///
+    /// The answer with the first `hints_used` letters revealed,
+    /// and all other letters hidden. `None` if no hints have been used
+    /// 
+    pub hint: Option<String>,
+    pub hints_used: i64,
+    pub attempts_left: i64,
+}
+
+///
+/// A single guess made on a riddle quest, for the admin view
+/// 
+#[derive(Serialize)]
+pub struct RiddleGuessModel {
+    pub user_id: i64,
+    pub quest_id: i64,
+    pub riddle_idx: i64,
+    pub guess: String,
+    pub correct: bool,
+    pub created_on: NaiveDateTime,
 }
 
 #[derive(Debug, Serialize)]
@@ -38,8 +109,12 @@ pub struct QuestConsequences {
 pub enum RiddleStatus {
     #[serde(rename="correct")]
     Correct(QuestReward),
+    #[serde(rename="close")]
+    Close { attempts_left: i64 },
     #[serde(rename="incorrect")]
-    Incorrect,
+    Incorrect { attempts_left: i64 },
+    #[serde(rename="failed")]
+    Failed(QuestConsequences),
 }
 
 #[derive(Serialize)]
@@ -0,0 +1,34 @@
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize)]
+pub struct QuestSettings {
+    ///
+    /// The number of free riddle hints each player may use per day
+    /// 
+    pub daily_hint_allowance: i64,
+    ///
+    /// The tag of the item consumed for a hint once the daily allowance is spent
+    /// 
+    pub hint_item_tag: String,
+    ///
+    /// The reduction in chance of receiving a card for each hint used on a riddle
+    /// 
+    pub hint_card_chance_penalty: f64,
+    ///
+    /// The maximum number of typos accepted in a correct riddle answer
+    /// 
+    pub riddle_typo_tolerance: usize,
+    ///
+    /// The maximum number of typos for an incorrect riddle answer to be reported as close.
+    /// Answers shorter than three times this allow proportionally fewer
+    /// 
+    pub riddle_close_distance: usize,
+    ///
+    /// The number of guesses a player has to answer a riddle, before the quest is failed
+    /// 
+    pub max_riddle_attempts: i64,
+    ///
+    /// The number of monster battles a player can win a day, before they are exhausted
+    /// 
+    pub daily_battle_limit: i64,
+}
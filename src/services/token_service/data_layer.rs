@@ -0,0 +1,57 @@
+use axum::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use derive_more::Constructor;
+use sqlx::SqlitePool;
+
+use crate::data_layer_error::Result;
+
+use super::models::Role;
+
+#[async_trait]
+pub trait TokenDataLayer : Send + Sync {
+    ///
+    /// Checks if the access token with the given `jti` has been revoked
+    /// 
+    async fn is_jti_revoked<'a>(&self, jti: &'a str) -> Result<bool>;
+    ///
+    /// Adds the access token with the given `jti` to the deny-list, until it `expires`
+    /// 
+    async fn revoke_jti<'a>(&self, jti: &'a str, user_id: i64, expires: NaiveDateTime) -> Result<()>;
+    ///
+    /// Gets the current role of the user, or `None` if the user doesn't exist
+    /// 
+    async fn get_user_role(&self, user_id: i64) -> Result<Option<Role>>;
+}
+
+#[derive(Constructor)]
+pub struct DbTokenDataLayer {
+    db: SqlitePool
+}
+
+#[async_trait]
+impl TokenDataLayer for DbTokenDataLayer {
+    async fn is_jti_revoked<'a>(&self, jti: &'a str) -> Result<bool> {
+        Ok(
+            sqlx::query!("SELECT jti FROM revoked_access_tokens WHERE jti = ?", jti)
+                .fetch_optional(&self.db).await?.is_some()
+        )
+    }
+
+    async fn revoke_jti<'a>(&self, jti: &'a str, user_id: i64, expires: NaiveDateTime) -> Result<()> {
+        let now = Utc::now().naive_utc();
+        sqlx::query!("
+            INSERT INTO revoked_access_tokens (jti, user_id, revoked_on, expires) VALUES (?, ?, ?, ?)
+            ON CONFLICT DO NOTHING
+            ", jti, user_id, now, expires
+        ).execute(&self.db).await?;
+        Ok(())
+    }
+
+    async fn get_user_role(&self, user_id: i64) -> Result<Option<Role>> {
+        Ok(
+            sqlx::query!(r#"SELECT role as "role: Role" FROM users WHERE id = ?"#, user_id)
+                .fetch_optional(&self.db).await?
+                .map(|user| user.role)
+        )
+    }
+}
@@ -2,6 +2,8 @@ use axum::{response::{IntoResponse, Response}, http::StatusCode};
 use log::error;
 use thiserror::Error;
 
+use crate::data_layer_error::DataLayerError;
+
 pub type Result<T> = std::result::Result<T, TokenError>;
 
 #[derive(Error, Debug)]
@@ -9,12 +11,31 @@ pub enum TokenError {
     #[error("Refresh token stale - please login again")]
     TokenStale,
     #[error("An error has occurred")]
-    JwtError(jwt::Error)
+    JwtError(jwt::Error),
+    #[error("Access token is missing the `{0}` claim")]
+    MissingClaim(&'static str),
+    #[error("Access token has an invalid subject `{0}`")]
+    InvalidSubject(String),
+    #[error("Access token has an invalid expiration `{0}`")]
+    InvalidExpiration(u64),
+    #[error("Access token has been revoked - please login again")]
+    TokenRevoked,
+    #[error("An internal server error has occurred")]
+    DataLayerError(DataLayerError),
+}
+
+impl From<DataLayerError> for TokenError {
+    fn from(value: DataLayerError) -> Self {
+        TokenError::DataLayerError(value)
+    }
 }
 
 impl IntoResponse for TokenError {
     fn into_response(self) -> Response {
         error!("{:?}", self);
+        if let TokenError::DataLayerError(_) = &self {
+            return (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()).into_response();
+        }
         (StatusCode::UNAUTHORIZED, self.to_string()).into_response()
     }
-}
\ No newline at end of file
+}
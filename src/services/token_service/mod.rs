@@ -1,162 +1,376 @@
+pub mod data_layer;
 pub mod error;
 pub mod models;
 pub mod settings;
 
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, sync::Arc};
 
+use axum::async_trait;
 use base64::{engine::general_purpose, Engine};
 use chrono::{Duration, Utc, DateTime};
 use derive_more::Constructor;
-use dotenv_codegen::dotenv;
 use dotenvy::dotenv;
 use hmac::{Hmac, Mac};
-use jwt::{SignWithKey, VerifyWithKey};
+use jwt::{RegisteredClaims, SignWithStore, VerifyWithStore};
 use lazy_static::lazy_static;
 use rand::{rngs::OsRng, Rng};
-use sha2::Sha256;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 
 
-use self::{settings::TokenSettings, error::{Result, TokenError}, models::AuthTokensModel};
+use self::{data_layer::TokenDataLayer, settings::TokenSettings, error::{Result, TokenError}, models::{AuthClaimsModel, AuthTokensModel, Role}};
 
 const REFRESH_TOKEN_LENGTH: usize = 128;
+const CSRF_TOKEN_LENGTH: usize = 32;
 
 lazy_static! {
-    static ref JWT_SECRET: String = {
-        dotenv().ok().expect(".env file must be provided");
-        dotenv!("JWT_SECRET").to_string()
+    ///
+    /// The active JWT signing keys by key ID, parsed from the comma separated
+    /// `kid:secret` pairs of `JWT_KEYS`. Every listed key is accepted for verification,
+    /// which allows keys to be rotated without invalidating existing tokens. The keys are
+    /// read when the server starts, so rotating them only needs a restart
+    ///
+    static ref JWT_KEYS: BTreeMap<String, Hmac<Sha256>> = {
+        dotenv().ok();
+        let keys = dotenvy::var("JWT_KEYS").expect("JWT_KEYS must be set to comma separated `kid:secret` pairs");
+        parse_jwt_keys(&keys)
     };
 }
 
+///
+/// Loads the JWT signing keys, panicking if `JWT_KEYS` is missing or malformed. Called
+/// at startup, so a bad key set is caught before any token is issued or verified
+///
+pub fn load_jwt_keys() {
+    lazy_static::initialize(&JWT_KEYS);
+}
+
+///
+/// The claims of an access token, ie. the registered claims along with the user's role
+///
+#[derive(Default, Deserialize, Serialize)]
+struct AccessTokenClaims {
+    #[serde(flatten)]
+    registered: RegisteredClaims,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<Role>,
+}
+
+#[async_trait]
 pub trait TokenService: Send + Sync {
     ///
-    /// Using the given `info`, generates a JWT and a series of series of
+    /// Generates a JWT for the user with the given `role`, and a series of
     /// random bytes representing a refresh token.
     ///
-    fn generate_auth_tokens(&self, user_id: i64) -> AuthTokensModel;
+    fn generate_auth_tokens(&self, user_id: i64, role: Role) -> Result<AuthTokensModel>;
 
     ///
-    /// Verifies a JWT `token`, and returns the corresponding user ID from the its content section with successful verification.
-    /// Returns the associated user ID, or `Error` in the event of unsuccessful verification
+    /// Verifies a JWT `token`, and returns the corresponding user ID and role from the its content section with successful verification.
+    /// Returns `Error` in the event of unsuccessful verification
+    ///
+    async fn verify_access_token(&self, access_token: &str) -> Result<AuthClaimsModel>;
+
     ///
-    fn verify_access_token(&self, access_token: &str) -> Result<i64>;
+    /// Adds the JWT `access_token` to the deny-list, so that it is rejected by `verify_access_token`
+    /// for the remainder of its lifetime
+    ///
+    async fn revoke_access_token(&self, access_token: &str) -> Result<()>;
+
+    ///
+    /// Gets the current role of the user, which may differ from the role in their access token
+    /// if it has changed since. Returns `None` if the user doesn't exist
+    ///
+    async fn get_current_role(&self, user_id: i64) -> Result<Option<Role>>;
 }
 
 #[derive(Clone, Constructor)]
 pub struct CoreTokenService {
-    settings: TokenSettings
+    settings: TokenSettings,
+    data_layer: Arc<dyn TokenDataLayer>,
 }
 
+#[async_trait]
 impl TokenService for CoreTokenService {
-    fn generate_auth_tokens(&self, user_id: i64) -> AuthTokensModel {
-        let key: Hmac<Sha256> = Hmac::new_from_slice(JWT_SECRET.as_bytes())
-            .expect("error converting SECRET into Hmac<Sha26>");
-
-        let mut claims = BTreeMap::new();
-        claims.insert("user_id", user_id.to_string());
-
-        let expires = (chrono::Utc::now() + Duration::seconds(self.settings.jwt_lifetime_s)).to_rfc3339();
-        claims.insert("expires", expires);
+    fn generate_auth_tokens(&self, user_id: i64, role: Role) -> Result<AuthTokensModel> {
+        let now = Utc::now();
+        let expires = now + Duration::seconds(self.settings.jwt_lifetime_s);
+
+        let claims = AccessTokenClaims {
+            registered: RegisteredClaims {
+                subject: Some(user_id.to_string()),
+                expiration: Some(expires.timestamp() as u64),
+                issued_at: Some(now.timestamp() as u64),
+                json_web_token_id: Some(Uuid::new_v4().to_string()),
+                ..Default::default()
+            },
+            role: Some(role),
+        };
 
-        let access_token = claims.sign_with_key(&key).expect("error signing jwt key");
-        let refresh_token = generate_random_bytes();
+        let access_token = (self.settings.jwt_signing_kid.as_str(), claims)
+            .sign_with_store(&*JWT_KEYS)
+            .map_err(TokenError::JwtError)?;
+        let refresh_token = generate_random_bytes(REFRESH_TOKEN_LENGTH);
 
-        AuthTokensModel {
+        Ok(AuthTokensModel {
             access_token,
             refresh_token,
+        })
+    }
+
+    async fn verify_access_token(&self, token: &str) -> Result<AuthClaimsModel> {
+        let AccessTokenClaims { registered: claims, role } = verify_claims(token)?;
+
+        // Check the expires parameter, and return error if the token is stale
+        if Utc::now() > expiration(&claims)? {
+            return Err(TokenError::TokenStale);
         }
+
+        let user_id = subject(&claims)?;
+        let role = role.ok_or(TokenError::MissingClaim("role"))?;
+
+        // Reject tokens that have been revoked before expiring
+        if self.data_layer.is_jti_revoked(jti(&claims)?).await? {
+            return Err(TokenError::TokenRevoked);
+        }
+
+        Ok(AuthClaimsModel { user_id, role })
     }
 
-    fn verify_access_token(&self, token: &str) -> Result<i64> {
-        // Convert the JWT_SECRET env variable into a Hmac hasher
-        let key: Hmac<Sha256> = Hmac::new_from_slice(JWT_SECRET.as_bytes())
-            .expect("error converting SECRET into Hmac<Sha256>");
-
-        // Verify the JWT using the hash key
-        let claims: std::result::Result<BTreeMap<String, String>, jwt::Error> = token.verify_with_key(&key);
-
-        // If successful verification...
-        return match claims {
-            Ok(mut claims) => {
-                // Check the expires parameter, and return error if the token is stale
-                if Utc::now() > DateTime::parse_from_rfc3339(&claims["expires"]).unwrap() {
-                    return Err(TokenError::TokenStale);
-
-                // Otherwise, return Ok with the user ID from the token
-                } else {
-                    return Ok(claims.remove("user_id").unwrap().parse::<i64>().unwrap());
-                }
-            },
-            Err(e) => Err(TokenError::JwtError(e))
-        };
+    async fn revoke_access_token(&self, token: &str) -> Result<()> {
+        let claims = verify_claims(token)?.registered;
+        let expires = expiration(&claims)?;
+
+        // Stale tokens are already rejected, so there is nothing to deny
+        if Utc::now() > expires {
+            return Ok(());
+        }
+
+        self.data_layer.revoke_jti(jti(&claims)?, subject(&claims)?, expires.naive_utc()).await?;
+        Ok(())
+    }
+
+    async fn get_current_role(&self, user_id: i64) -> Result<Option<Role>> {
+        Ok(self.data_layer.get_user_role(user_id).await?)
     }
 }
 
 ///
-/// Generates a series of random, OS bytes, with a length equal to `REFRESH_TOKEN_LENGTH`
+/// Parses the comma separated `kid:secret` pairs of `keys` into a key store
+///
+fn parse_jwt_keys(keys: &str) -> BTreeMap<String, Hmac<Sha256>> {
+    keys.split(',')
+        .map(|pair| {
+            let (kid, secret) = pair.trim().split_once(':')
+                .filter(|(kid, secret)| !kid.is_empty() && !secret.is_empty())
+                .expect("JWT_KEYS entries must be formatted as `kid:secret`");
+            let key = Hmac::new_from_slice(secret.as_bytes())
+                .expect("error converting JWT key into Hmac<Sha256>");
+            (kid.to_string(), key)
+        })
+        .collect()
+}
+
+///
+/// Verifies the signature of `token` against the key named by its `kid` header,
+/// returning its claims
+///
+fn verify_claims(token: &str) -> Result<AccessTokenClaims> {
+    token.verify_with_store(&*JWT_KEYS).map_err(TokenError::JwtError)
+}
+
+fn expiration(claims: &RegisteredClaims) -> Result<DateTime<Utc>> {
+    let exp = claims.expiration.ok_or(TokenError::MissingClaim("exp"))?;
+    i64::try_from(exp).ok()
+        .and_then(|exp| DateTime::from_timestamp(exp, 0))
+        .ok_or(TokenError::InvalidExpiration(exp))
+}
+
+fn subject(claims: &RegisteredClaims) -> Result<i64> {
+    let sub = claims.subject.as_ref().ok_or(TokenError::MissingClaim("sub"))?;
+    sub.parse::<i64>().map_err(|_| TokenError::InvalidSubject(sub.clone()))
+}
+
+fn jti(claims: &RegisteredClaims) -> Result<&str> {
+    claims.json_web_token_id.as_deref().ok_or(TokenError::MissingClaim("jti"))
+}
+
+///
+/// Generates a series of random, OS bytes, with a length equal to `length`
 ///
-fn generate_random_bytes() -> String {
+fn generate_random_bytes(length: usize) -> String {
     let mut rng = OsRng::default();
-    let mut bytes = [0u8; REFRESH_TOKEN_LENGTH];
-    rng.fill(&mut bytes);
+    let mut bytes = vec![0u8; length];
+    rng.fill(&mut bytes[..]);
 
     general_purpose::STANDARD_NO_PAD.encode(bytes)
 }
 
+///
+/// Generates a random token for the double-submit CSRF cookie. The client must echo
+/// it back in a header, which a cross-site request is unable to read and forge
+///
+pub fn generate_csrf_token() -> String {
+    generate_random_bytes(CSRF_TOKEN_LENGTH)
+}
+
+///
+/// Returns the hex encoded SHA-256 digest of the `token`. Tokens handed to clients
+/// are only stored by digest, so a leaked database can't be used to sign in
+///
+pub fn digest_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::{collections::HashSet, sync::Mutex};
     use base64::engine::general_purpose;
-    use serde::{Deserialize, Serialize};
+    use chrono::NaiveDateTime;
+    use crate::data_layer_error::Result as DataLayerResult;
+    use super::settings::CookieSameSite;
 
-    #[derive(Deserialize, Serialize)]
-    struct TokenContents {
-        user_id: String,
-        expires: String,
+    #[derive(Default)]
+    struct MockTokenDataLayer {
+        revoked: Mutex<HashSet<String>>,
     }
 
-    #[test]
-    fn test_token_gen_and_verify() {
+    #[async_trait]
+    impl TokenDataLayer for MockTokenDataLayer {
+        async fn is_jti_revoked<'a>(&self, jti: &'a str) -> DataLayerResult<bool> {
+            Ok(self.revoked.lock().unwrap().contains(jti))
+        }
+
+        async fn revoke_jti<'a>(&self, jti: &'a str, _user_id: i64, _expires: NaiveDateTime) -> DataLayerResult<()> {
+            self.revoked.lock().unwrap().insert(jti.to_string());
+            Ok(())
+        }
+
+        async fn get_user_role(&self, _user_id: i64) -> DataLayerResult<Option<Role>> {
+            Ok(Some(Role::Player))
+        }
+    }
 
-        let svc = CoreTokenService { settings: TokenSettings { jwt_lifetime_s: 5, refr_token_lifetime_s: 5 }};
+    fn create_svc() -> CoreTokenService {
+        let settings = TokenSettings {
+            jwt_lifetime_s: 5,
+            refr_token_lifetime_s: 5,
+            jwt_signing_kid: "k1".to_string(),
+            cookie_secure: true,
+            cookie_same_site: CookieSameSite::Strict,
+            refr_cookie_path: "/".to_string(),
+        };
+        CoreTokenService::new(settings, Arc::new(MockTokenDataLayer::default()))
+    }
+
+    #[tokio::test]
+    async fn test_token_gen_and_verify() {
+        let svc = create_svc();
         let user_id = 10;
 
-        let tokens = svc.generate_auth_tokens(user_id);
+        let tokens = svc.generate_auth_tokens(user_id, Role::GameMaster).unwrap();
 
-        let token_user_id = svc.verify_access_token(&tokens.access_token);
+        let token_user_id = svc.verify_access_token(&tokens.access_token).await;
         assert!(token_user_id.is_ok());
 
-        let token_user_id = token_user_id.unwrap();
-        assert_eq!(token_user_id, user_id);
+        let claims = token_user_id.unwrap();
+        assert_eq!(claims.user_id, user_id);
+        assert_eq!(claims.role, Role::GameMaster);
     }
 
-    #[test]
-    fn test_improper_token() {
-        let svc = CoreTokenService { settings: TokenSettings { jwt_lifetime_s: 5, refr_token_lifetime_s: 5 }};
+    #[tokio::test]
+    async fn test_improper_token() {
+        let svc = create_svc();
         let user_id = 10;
-        let tokens = svc.generate_auth_tokens(user_id);
+        let tokens = svc.generate_auth_tokens(user_id, Role::Player).unwrap();
 
-        // Grab the content of the JWT, deserialize it, and update the role to "Admin"
+        // Grab the content of the JWT, deserialize it, and update the role to admin
         // to attempt to hack the role requirements
-        let str = tokens.access_token.split('.').skip(1).next().unwrap();
-        let str = general_purpose::STANDARD_NO_PAD.decode(str).unwrap();
-        let mut contents: TokenContents =
-            serde_json::from_str(&String::from_utf8_lossy(&str).to_string()).unwrap();
+        let str = tokens.access_token.split('.').nth(1).unwrap();
+        let str = general_purpose::URL_SAFE_NO_PAD.decode(str).unwrap();
+        let mut contents: AccessTokenClaims =
+            serde_json::from_str(&String::from_utf8_lossy(&str)).unwrap();
 
-        contents.user_id = "10".to_string();
+        contents.role = Some(Role::Admin);
 
         // Build the new token with the new role, but with the same
         // header and key
         let new_token = format!(
             "{}.{}.{}",
             tokens.access_token.split('.').next().unwrap(),
-            general_purpose::STANDARD_NO_PAD.encode(serde_json::to_string(&contents).unwrap()),
-            tokens.access_token.split('.').skip(2).next().unwrap()
+            general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_string(&contents).unwrap()),
+            tokens.access_token.split('.').nth(2).unwrap()
         );
 
         // Assert that an error is thrown when the token is attempted to
         // be verified
-        let verified_info = svc.verify_access_token(&new_token);
+        let verified_info = svc.verify_access_token(&new_token).await;
         assert!(verified_info.is_err());
     }
-}
 
+    #[tokio::test]
+    async fn test_missing_and_invalid_claims() {
+        let svc = create_svc();
+        let expiration = Some((Utc::now() + Duration::seconds(5)).timestamp() as u64);
+
+        // Properly signed tokens with missing or malformed claims must be rejected without panicking
+        let no_sub = ("k1", RegisteredClaims { expiration, json_web_token_id: Some("a".to_string()), ..Default::default() })
+            .sign_with_store(&*JWT_KEYS).unwrap();
+        assert!(matches!(svc.verify_access_token(&no_sub).await, Err(TokenError::MissingClaim("sub"))));
+
+        let bad_sub = ("k1", RegisteredClaims { expiration, subject: Some("ten".to_string()), json_web_token_id: Some("b".to_string()), ..Default::default() })
+            .sign_with_store(&*JWT_KEYS).unwrap();
+        assert!(matches!(svc.verify_access_token(&bad_sub).await, Err(TokenError::InvalidSubject(_))));
+
+        let no_exp = ("k1", RegisteredClaims { subject: Some("10".to_string()), json_web_token_id: Some("c".to_string()), ..Default::default() })
+            .sign_with_store(&*JWT_KEYS).unwrap();
+        assert!(matches!(svc.verify_access_token(&no_exp).await, Err(TokenError::MissingClaim("exp"))));
+
+        let no_role = ("k1", RegisteredClaims { expiration, subject: Some("10".to_string()), json_web_token_id: Some("d".to_string()), ..Default::default() })
+            .sign_with_store(&*JWT_KEYS).unwrap();
+        assert!(matches!(svc.verify_access_token(&no_role).await, Err(TokenError::MissingClaim("role"))));
+
+        // Tokens signed with an unknown key ID must be rejected
+        let store = parse_jwt_keys("k2:other_secret");
+        let unknown_kid = ("k2", RegisteredClaims { expiration, subject: Some("10".to_string()), ..Default::default() })
+            .sign_with_store(&store).unwrap();
+        assert!(matches!(svc.verify_access_token(&unknown_kid).await, Err(TokenError::JwtError(_))));
+
+        assert!(svc.verify_access_token("not.a.jwt").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_access_token() {
+        let svc = create_svc();
+        let revoked = svc.generate_auth_tokens(10, Role::Player).unwrap();
+        let other = svc.generate_auth_tokens(10, Role::Player).unwrap();
+
+        svc.revoke_access_token(&revoked.access_token).await.unwrap();
+
+        // Only the revoked token is denied
+        assert!(matches!(svc.verify_access_token(&revoked.access_token).await, Err(TokenError::TokenRevoked)));
+        assert_eq!(svc.verify_access_token(&other.access_token).await.unwrap().user_id, 10);
+    }
+
+    #[test]
+    fn test_parse_jwt_keys() {
+        let keys = parse_jwt_keys("k1:secret, k2:other:secret");
+        assert_eq!(keys.keys().collect::<Vec<_>>(), vec!["k1", "k2"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "JWT_KEYS entries must be formatted as `kid:secret`")]
+    fn test_parse_malformed_jwt_keys() {
+        parse_jwt_keys("k1:secret, k2:");
+    }
+
+    #[test]
+    fn test_digest_token() {
+        let digest = digest_token("abc");
+        assert_eq!(digest, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        // The digests of different tokens must differ
+        assert_ne!(digest_token("abd"), digest);
+    }
+}
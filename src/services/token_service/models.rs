@@ -1,4 +1,28 @@
+use serde::{Deserialize, Serialize};
+
 pub struct AuthTokensModel {
     pub access_token: String,
     pub refresh_token: String
 }
+
+///
+/// The role of a user, granting access to everything the lower roles can access
+/// 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    Player,
+    GameMaster,
+    Admin,
+}
+
+///
+/// The user and role an access token was issued to
+/// 
+#[derive(Debug, Clone, Copy)]
+pub struct AuthClaimsModel {
+    pub user_id: i64,
+    pub role: Role,
+}
@@ -1,7 +1,35 @@
 use serde::Deserialize;
+use tower_cookies::cookie::SameSite;
 
 #[derive(Clone, Deserialize)]
 pub struct TokenSettings {
     pub jwt_lifetime_s: i64,
     pub refr_token_lifetime_s: i64,
-}
\ No newline at end of file
+    /// Key ID (from `JWT_KEYS`) used to sign new access tokens
+    pub jwt_signing_kid: String,
+    /// Whether the refresh and CSRF cookies are only sent over HTTPS
+    pub cookie_secure: bool,
+    pub cookie_same_site: CookieSameSite,
+    /// Path the refresh token cookie is scoped to
+    pub refr_cookie_path: String,
+}
+
+///
+/// The `SameSite` attribute of the refresh and CSRF cookies
+///
+#[derive(Clone, Copy, Deserialize)]
+pub enum CookieSameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl From<CookieSameSite> for SameSite {
+    fn from(value: CookieSameSite) -> Self {
+        match value {
+            CookieSameSite::Strict => SameSite::Strict,
+            CookieSameSite::Lax => SameSite::Lax,
+            CookieSameSite::None => SameSite::None,
+        }
+    }
+}